@@ -0,0 +1,595 @@
+//! Sidereal time and positions on the sky, mostly through libastro.
+//!
+//! Times are decimal hours and angles are degrees unless stated otherwise. Note that libastro's
+//! "modified Julian date" counts days from 1899 December 31 12h UT, not from 1858 November 17.
+
+use std::{ffi::CStr, fmt};
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use clap::ValueEnum;
+use libastro_sys::{cal_mjd, cns_name, cns_pick, ecl_eq, sunpos, utc_gst};
+
+use crate::{profile, time::normalize_hours};
+
+/// Convert a time of day to decimal hours in the range `[0, 24)`.
+#[must_use]
+pub fn utc_to_float(time: NaiveTime) -> f64 {
+	// Chrono represents a leap second as second 59 with a nanosecond value of 1e9 or more. Holding
+	// the clock at the end of second 59 for its duration keeps the result inside the minute, and
+	// inside the day for a leap second at 23:59:60.
+	let nanosecond = time.nanosecond().min(999_999_999);
+	f64::from(time.hour())
+		+ (f64::from(time.minute()) / (60.0))
+		+ (f64::from(time.second()) / (60.0 * 60.0))
+		+ (f64::from(nanosecond) / (60.0 * 60.0 * 1_000_000_000.0))
+}
+
+/// The arguments libastro's `cal_mjd` takes for the date.
+fn cal_mjd_arguments(date: NaiveDate) -> (i32, f64, i32) {
+	let mn = i32::try_from(date.month()).unwrap();
+	(mn, f64::from(date.day()), date.year())
+}
+
+fn call_cal_mjd(mn: i32, dy: f64, yr: i32) -> f64 {
+	let mut mjd = 0.0;
+	profile::time("cal_mjd", || unsafe {
+		cal_mjd(mn, dy, yr, std::ptr::addr_of_mut!(mjd));
+	});
+	mjd
+}
+
+fn call_utc_gst(mjd: f64, utc: f64) -> f64 {
+	let mut gst = 0.0;
+	profile::time("utc_gst", || unsafe {
+		utc_gst(mjd, utc, std::ptr::addr_of_mut!(gst));
+	});
+	gst
+}
+
+/// libastro's modified Julian date at 0h UT of the date.
+#[must_use]
+pub fn mjd_from_gregorian_date(date: NaiveDate) -> f64 {
+	let (mn, dy, yr) = cal_mjd_arguments(date);
+	call_cal_mjd(mn, dy, yr)
+}
+
+/// libastro's modified Julian date at 0h UT of the date, computed in Rust from the days since its
+/// epoch. It agrees with [`mjd_from_gregorian_date`] from 1582 October 15 on, where libastro
+/// switches from the Julian calendar to the Gregorian one.
+#[must_use]
+pub fn mjd_from_gregorian_date_native(date: NaiveDate) -> f64 {
+	// libastro's epoch falls at noon, half a day after the start of 1899 December 31.
+	let epoch = NaiveDate::from_ymd_opt(1899, 12, 31).expect("valid epoch");
+	(date - epoch).num_days() as f64 - 0.5
+}
+
+/// libastro's modified Julian date of an instant, in days.
+#[must_use]
+pub fn mjd_from_gregorian_datetime(datetime: NaiveDateTime) -> f64 {
+	let mjd = mjd_from_gregorian_date(datetime.date());
+	mjd + utc_to_float(datetime.time()) / 24.0
+}
+
+/// Greenwich mean sidereal time from libastro's `utc_gst`.
+///
+/// A finite result outside `[0, 24)` is normalized into it. NaN or infinity is passed on, to be
+/// caught by [`checked_sidereal_time`].
+#[must_use]
+pub fn greenwich_mean_sidereal_time(datetime: NaiveDateTime) -> f64 {
+	// `utc_gst` wants the MJD at 0h UT, which `cal_mjd` gives for the date as it is.
+	let utc = utc_to_float(datetime.time());
+	let mjd = mjd_from_gregorian_date(datetime.date());
+	normalize_hours(call_utc_gst(mjd, utc))
+}
+
+/// The raw inputs and outputs of the `cal_mjd` and `utc_gst` calls behind
+/// [`greenwich_mean_sidereal_time`], one call per line, for comparing libastro across platforms.
+#[must_use]
+pub fn trace_ffi(datetime: NaiveDateTime) -> String {
+	let (mn, dy, yr) = cal_mjd_arguments(datetime.date());
+	let mjd = call_cal_mjd(mn, dy, yr);
+	let utc = utc_to_float(datetime.time());
+	let gst = call_utc_gst(mjd, utc);
+	format!(
+		"cal_mjd(mn = {mn}, dy = {dy:?}, yr = {yr}) -> mjd = {mjd:?}\nutc_gst(mjd = {mjd:?}, utc \
+		 = {utc:?}) -> gst = {gst:?}\n"
+	)
+}
+
+/// A sidereal time that wasn't a finite number, with the instant it was computed for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InvalidSiderealTime {
+	pub datetime: NaiveDateTime,
+	pub value: f64,
+}
+
+impl fmt::Display for InvalidSiderealTime {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"Sidereal time for {} came out as {} (MJD {}, UT {}h)",
+			self.datetime,
+			self.value,
+			mjd_from_gregorian_date(self.datetime.date()),
+			utc_to_float(self.datetime.time())
+		)
+	}
+}
+
+impl std::error::Error for InvalidSiderealTime {}
+
+/// Check a sidereal time in hours computed for `datetime`, normalizing finite values into
+/// `[0, 24)`.
+///
+/// # Errors
+///
+/// Fails when the value is NaN or infinite.
+pub fn checked_sidereal_time(
+	value: f64,
+	datetime: NaiveDateTime,
+) -> Result<f64, InvalidSiderealTime> {
+	if value.is_finite() {
+		Ok(normalize_hours(value))
+	} else {
+		Err(InvalidSiderealTime { datetime, value })
+	}
+}
+
+/// Julian Date of the J2000.0 epoch.
+pub const J2000: f64 = 2_451_545.0;
+
+/// Julian Date of a UTC instant, treating UTC as UT1.
+#[must_use]
+pub fn julian_date(datetime: NaiveDateTime) -> f64 {
+	const UNIX_EPOCH_JD: f64 = 2_440_587.5;
+	let datetime = datetime.and_utc();
+	let seconds = datetime.timestamp() as f64
+		+ f64::from(datetime.timestamp_subsec_nanos()) / 1_000_000_000.0;
+	UNIX_EPOCH_JD + seconds / 86_400.0
+}
+
+/// GMST in hours from the IAU 2006 expression: the Earth rotation angle plus a polynomial in
+/// Julian centuries, as given by Capitaine et al. (2003).
+///
+/// UTC stands in for both UT1 and TT. The UT1 error is under a second and the TT offset only
+/// feeds the polynomial, where it contributes well under a millisecond.
+#[must_use]
+pub fn greenwich_mean_sidereal_time_iau2006(datetime: NaiveDateTime) -> f64 {
+	let du = julian_date(datetime) - J2000;
+	let t = du / 36_525.0;
+	// Splitting off the whole days keeps the large multiple of a full turn out of the product.
+	let era_turns = 0.779_057_273_264_0 + 0.002_737_811_911_354_48 * du + du.fract();
+	let polynomial_arcsec = 0.014_506
+		+ t * (4_612.156_534
+			+ t * (1.391_581_7
+				+ t * (-0.000_000_44 + t * (-0.000_029_956 + t * -0.000_000_036_8))));
+	normalize_hours(24.0 * era_turns.fract() + polynomial_arcsec / 15.0 / 3600.0)
+}
+
+/// GMST in hours from the IAU 1982 expression of Aoki et al. (1982): a cubic in Julian centuries
+/// for 0h UT, plus UT scaled to sidereal time.
+///
+/// UTC stands in for UT1.
+#[must_use]
+pub fn greenwich_mean_sidereal_time_iau1982(datetime: NaiveDateTime) -> f64 {
+	let jd = julian_date(datetime);
+	// Julian Dates start at noon, so 0h UT is half a day off a whole number.
+	let jd_at_0h = (jd - 0.5).floor() + 0.5;
+	let t = (jd_at_0h - J2000) / 36_525.0;
+	let seconds_at_0h =
+		24_110.548_41 + t * (8_640_184.812_866 + t * (0.093_104 + t * -0.000_006_2));
+	let ut_seconds = (jd - jd_at_0h) * 86_400.0;
+	normalize_hours((seconds_at_0h + SIDEREAL_DAYS_PER_SOLAR_DAY * ut_seconds) / 3600.0)
+}
+
+/// The model behind Greenwich mean sidereal time. The models agree to well under a second
+/// today and drift apart by a few milliseconds a century.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum GmstAlgorithm {
+	/// libastro's `utc_gst`, the default
+	Libastro,
+	/// The IAU 1982 cubic of Aoki et al., computed in Rust
+	Iau1982,
+	/// The IAU 2006 polynomial on the Earth rotation angle, computed in Rust
+	Iau2006,
+}
+
+#[must_use]
+pub fn greenwich_mean_sidereal_time_with(datetime: NaiveDateTime, algorithm: GmstAlgorithm) -> f64 {
+	match algorithm {
+		GmstAlgorithm::Libastro => greenwich_mean_sidereal_time(datetime),
+		GmstAlgorithm::Iau1982 => greenwich_mean_sidereal_time_iau1982(datetime),
+		GmstAlgorithm::Iau2006 => greenwich_mean_sidereal_time_iau2006(datetime),
+	}
+}
+
+/// How a body's prime meridian turns relative to the stars, from the IAU WGCCRE reports.
+pub struct Rotation {
+	/// The angle W of the prime meridian at J2000.0 in degrees, measured eastward along the
+	/// body's equator from its ascending node on the ICRF equator.
+	pub prime_meridian_at_j2000: f64,
+	/// Sidereal rotation rate in degrees per day.
+	pub degrees_per_day: f64,
+}
+
+/// Mars' rotation from the WGCCRE 2009 report (Archinal et al. 2011).
+pub const MARS_ROTATION: Rotation = Rotation {
+	prime_meridian_at_j2000: 176.630,
+	degrees_per_day: 350.891_982_26,
+};
+
+/// Sidereal time at a body's prime meridian in hours, where 24 hours are one sidereal rotation of
+/// that body.
+///
+/// The angle is a linear function of time, so precession of the body's pole and variations in its
+/// rotation are ignored. TT is taken to be UTC plus 69.184 s, which has been exact since 2017.
+#[must_use]
+pub fn prime_meridian_sidereal_time(datetime: NaiveDateTime, rotation: &Rotation) -> f64 {
+	const TT_MINUS_UTC_DAYS: f64 = 69.184 / 86_400.0;
+	let days = julian_date(datetime) + TT_MINUS_UTC_DAYS - J2000;
+	let angle = rotation.prime_meridian_at_j2000 + rotation.degrees_per_day * days;
+	normalize_hours(angle / 15.0)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Body {
+	Earth,
+	Mars,
+}
+
+/// Equatorial coordinates: right ascension in hours and declination in degrees.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Equatorial {
+	pub right_ascension: f64,
+	pub declination: f64,
+}
+
+/// Apparent position of the Sun.
+#[must_use]
+pub fn sun_equatorial(datetime: NaiveDateTime) -> Equatorial {
+	let mjd = mjd_from_gregorian_datetime(datetime);
+	let (mut lsn, mut rsn, mut bsn) = (0.0, 0.0, 0.0);
+	let (mut ra, mut dec) = (0.0, 0.0);
+	unsafe {
+		sunpos(
+			mjd,
+			std::ptr::addr_of_mut!(lsn),
+			std::ptr::addr_of_mut!(rsn),
+			std::ptr::addr_of_mut!(bsn),
+		);
+		ecl_eq(
+			mjd,
+			bsn,
+			lsn,
+			std::ptr::addr_of_mut!(ra),
+			std::ptr::addr_of_mut!(dec),
+		);
+	}
+	Equatorial {
+		right_ascension: ra.to_degrees() / 15.0,
+		declination: dec.to_degrees(),
+	}
+}
+
+/// One of the 88 IAU constellations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Constellation {
+	/// The three letter abbreviation, e.g. `UMa`.
+	pub abbreviation: String,
+	/// The full name, e.g. `Ursa Major`.
+	pub name: String,
+}
+
+/// The constellation containing a position on the sky, given in coordinates of the equinox at
+/// the libastro modified Julian date `epoch`.
+///
+/// libastro's `cns_pick` looks the position up in the IAU boundaries of Delporte (1930), as
+/// tabulated by Roman (1987), after precessing it to B1875.0 where the boundaries run along
+/// lines of constant right ascension and declination.
+#[must_use]
+pub fn constellation(position: Equatorial, epoch: f64) -> Option<Constellation> {
+	let ra = (position.right_ascension * 15.0).to_radians();
+	let dec = position.declination.to_radians();
+	let id = unsafe { cns_pick(ra, dec, epoch) };
+	if id < 0 {
+		return None;
+	}
+	// The names are static strings like `UMa: Ursa Major`.
+	let text = unsafe { CStr::from_ptr(cns_name(id)) }.to_str().ok()?;
+	let (abbreviation, name) = text.split_once(": ")?;
+	Some(Constellation {
+		abbreviation: abbreviation.to_owned(),
+		name: name.to_owned(),
+	})
+}
+
+/// Apparent right ascension of the Sun in hours.
+#[must_use]
+pub fn sun_right_ascension(datetime: NaiveDateTime) -> f64 {
+	sun_equatorial(datetime).right_ascension
+}
+
+/// The equation of time in hours: apparent minus mean solar time, in the range `[-12, 12)`.
+#[must_use]
+pub fn equation_of_time(datetime: NaiveDateTime) -> f64 {
+	// The Sun's hour angle at Greenwich is GMST - RA, and apparent solar time is that hour angle
+	// shifted by 12h so noon is when the Sun transits.
+	let apparent = greenwich_mean_sidereal_time(datetime) - sun_right_ascension(datetime) + 12.0;
+	let mean = utc_to_float(datetime.time());
+	normalize_hours(apparent - mean + 12.0) - 12.0
+}
+
+/// What a sundial at the given longitude would read, in hours.
+#[must_use]
+pub fn local_apparent_solar_time(datetime: NaiveDateTime, longitude: f64) -> f64 {
+	let local_mean_solar_time = utc_to_float(datetime.time()) + longitude / 15.0;
+	normalize_hours(local_mean_solar_time + equation_of_time(datetime))
+}
+
+/// Altitude and azimuth in degrees of an object at the given hour angle (hours) and declination
+/// (degrees), seen from the given latitude (degrees). Azimuth is measured from north through east.
+#[must_use]
+pub fn horizontal_coordinates(hour_angle: f64, declination: f64, latitude: f64) -> (f64, f64) {
+	let ha = (hour_angle * 15.0).to_radians();
+	let dec = declination.to_radians();
+	let lat = latitude.to_radians();
+	let altitude = (lat.sin() * dec.sin() + lat.cos() * dec.cos() * ha.cos()).asin();
+	let azimuth =
+		(-dec.cos() * ha.sin()).atan2(dec.sin() * lat.cos() - dec.cos() * ha.cos() * lat.sin());
+	(
+		altitude.to_degrees(),
+		azimuth.to_degrees().rem_euclid(360.0),
+	)
+}
+
+/// Flattening of the WGS84 ellipsoid.
+pub const WGS84_FLATTENING: f64 = 1.0 / 298.257_223_563;
+
+/// The geocentric latitude in degrees, the angle at the Earth's centre, of a point at the given
+/// geodetic latitude on the WGS84 ellipsoid. Away from the equator and poles it is up to about
+/// 0.19° closer to the equator.
+#[must_use]
+pub fn geocentric_latitude(geodetic: f64) -> f64 {
+	((1.0 - WGS84_FLATTENING).powi(2) * geodetic.to_radians().tan())
+		.atan()
+		.to_degrees()
+}
+
+/// Air at the observer, which sets how much refraction lifts objects near the horizon.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Atmosphere {
+	/// Degrees Celsius.
+	pub temperature: f64,
+	/// Hectopascals, the same as millibars.
+	pub pressure: f64,
+}
+
+impl Default for Atmosphere {
+	/// The standard conditions Sæmundsson's formula was fitted to.
+	fn default() -> Self {
+		Self {
+			temperature: 10.0,
+			pressure: 1010.0,
+		}
+	}
+}
+
+impl Atmosphere {
+	/// Degrees by which refraction raises an object at the given true altitude, from Sæmundsson's
+	/// formula (Meeus, Astronomical Algorithms, 16.4) scaled for temperature and pressure. It's
+	/// about 29′ on the horizon and a minute at 45°. The formula doesn't hold far below the
+	/// horizon, where nothing is corrected.
+	#[must_use]
+	pub fn refraction(&self, altitude: f64) -> f64 {
+		if altitude < -1.0 {
+			return 0.0;
+		}
+		let arcminutes = 1.02 / (altitude + 10.3 / (altitude + 5.11)).to_radians().tan();
+		let scale = (self.pressure / 1010.0) * (283.0 / (273.0 + self.temperature));
+		arcminutes * scale / 60.0
+	}
+}
+
+/// Sidereal days in one mean solar day, the rate at which sidereal time runs ahead of UT
+/// (Aoki et al. 1982).
+pub const SIDEREAL_DAYS_PER_SOLAR_DAY: f64 = 1.002_737_909_350_795;
+
+/// Sidereal days elapsed between two UTC instants, negative when `end` is before `start`.
+#[must_use]
+pub fn sidereal_days_between(start: NaiveDateTime, end: NaiveDateTime) -> f64 {
+	(julian_date(end) - julian_date(start)) * SIDEREAL_DAYS_PER_SOLAR_DAY
+}
+
+#[must_use]
+pub fn local_mean_sidereal_time(gmst: f64, longitude: f64) -> f64 {
+	normalize_hours(gmst + longitude / 15.0)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn at(date: (i32, u32, u32), time: (u32, u32, u32)) -> NaiveDateTime {
+		NaiveDate::from_ymd_opt(date.0, date.1, date.2)
+			.and_then(|date| date.and_hms_opt(time.0, time.1, time.2))
+			.expect("valid test instant")
+	}
+
+	/// The gap between two times of day in hours, the short way round midnight.
+	fn hours_apart(a: f64, b: f64) -> f64 {
+		(normalize_hours(a - b + 12.0) - 12.0).abs()
+	}
+
+	const DATES: [((i32, u32, u32), (u32, u32, u32)); 6] = [
+		((1950, 3, 1), (0, 0, 0)),
+		((1987, 4, 10), (19, 21, 0)),
+		((2000, 1, 1), (12, 0, 0)),
+		((2010, 6, 30), (23, 59, 59)),
+		((2024, 12, 31), (6, 0, 0)),
+		((2050, 8, 15), (18, 0, 0)),
+	];
+
+	#[test]
+	fn iau_models_agree_within_milliseconds() {
+		for (date, time) in DATES {
+			let datetime = at(date, time);
+			let gap = hours_apart(
+				greenwich_mean_sidereal_time_iau1982(datetime),
+				greenwich_mean_sidereal_time_iau2006(datetime),
+			);
+			assert!(gap * 3600.0 < 0.015, "{datetime}: {}s apart", gap * 3600.0);
+		}
+	}
+
+	#[test]
+	fn libastro_agrees_with_iau2006() {
+		// libastro's coefficients are rounded to about seven digits, which holds it to a tenth of
+		// a second or so of the IAU expressions over this century and the last.
+		for (date, time) in DATES {
+			let datetime = at(date, time);
+			let gap = hours_apart(
+				greenwich_mean_sidereal_time(datetime),
+				greenwich_mean_sidereal_time_iau2006(datetime),
+			);
+			assert!(gap * 3600.0 < 0.2, "{datetime}: {}s apart", gap * 3600.0);
+		}
+	}
+
+	#[test]
+	fn leap_second_stays_in_the_day() {
+		let last_nanosecond =
+			NaiveTime::from_hms_nano_opt(23, 59, 59, 999_999_999).expect("valid time");
+		for nanosecond in [1_000_000_000, 1_500_000_000, 1_999_999_999] {
+			let leap = NaiveTime::from_hms_nano_opt(23, 59, 59, nanosecond).expect("leap second");
+			let hours = utc_to_float(leap);
+			assert!(hours < 24.0, "{leap} gave {hours}");
+			assert!(
+				(hours - utc_to_float(last_nanosecond)).abs() < 1e-12,
+				"{leap} gave {hours}"
+			);
+		}
+		let midday_leap = NaiveTime::from_hms_nano_opt(11, 59, 59, 1_250_000_000).expect("leap");
+		assert!(utc_to_float(midday_leap) < 12.0);
+	}
+
+	#[test]
+	fn equation_of_time_peaks_in_early_november() {
+		// Sundials run furthest ahead of the clock around November 3, by about 16.4 minutes.
+		let minutes = equation_of_time(at((2023, 11, 3), (12, 0, 0))) * 60.0;
+		assert!((minutes - 16.4).abs() < 0.3, "{minutes} minutes");
+	}
+
+	#[test]
+	fn non_finite_sidereal_times_are_errors() {
+		let datetime = at((2024, 1, 1), (0, 0, 0));
+		for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+			let error = checked_sidereal_time(value, datetime).expect_err("not a time");
+			assert_eq!(error.datetime, datetime);
+			assert!(error.to_string().contains("2024-01-01"), "{error}");
+		}
+		assert_eq!(checked_sidereal_time(25.5, datetime), Ok(1.5));
+		assert_eq!(checked_sidereal_time(-1.0, datetime), Ok(23.0));
+	}
+
+	#[test]
+	fn refraction_lifts_the_horizon_by_29_arcminutes() {
+		let air = Atmosphere::default();
+		let horizon = air.refraction(0.0);
+		assert!((horizon * 60.0 - 28.9).abs() < 0.3, "{}′", horizon * 60.0);
+		// Bennett's formula goes the other way, from the apparent altitude, so it should give the
+		// same refraction back from where the object appears.
+		let bennett = |apparent: f64| 1.0 / (apparent + 7.31 / (apparent + 4.4)).to_radians().tan();
+		for altitude in [0.0, 0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 45.0, 80.0] {
+			let arcminutes = air.refraction(altitude) * 60.0;
+			let back = bennett(altitude + arcminutes / 60.0);
+			assert!(
+				(back - arcminutes).abs() < 0.1,
+				"{altitude}°: {arcminutes}′ against {back}′"
+			);
+		}
+		let at_45 = air.refraction(45.0) * 60.0;
+		assert!((0.9..1.1).contains(&at_45), "{at_45}′");
+		assert!(air.refraction(90.0).abs() < 1e-3);
+		assert_eq!(air.refraction(-5.0), 0.0);
+		let cold_and_high = Atmosphere {
+			temperature: -20.0,
+			pressure: 1040.0,
+		};
+		assert!(cold_and_high.refraction(0.0) > horizon);
+	}
+
+	#[test]
+	fn geocentric_latitude_is_lower_by_a_fifth_of_a_degree_at_45() {
+		let difference = 45.0 - geocentric_latitude(45.0);
+		assert!((difference - 0.1924).abs() < 1e-4, "{difference}°");
+		assert!((geocentric_latitude(-45.0) + 45.0 - difference).abs() < 1e-12);
+		assert_eq!(geocentric_latitude(0.0), 0.0);
+		assert!((geocentric_latitude(90.0) - 90.0).abs() < 1e-9);
+	}
+
+	#[test]
+	fn native_mjd_agrees_with_cal_mjd() {
+		let date =
+			|year, month, day| NaiveDate::from_ymd_opt(year, month, day).expect("valid date");
+		// Every day of four centuries, across each kind of leap year rule.
+		let disagreement = date(1900, 1, 1)
+			.iter_days()
+			.take_while(|date| date.year() < 2300)
+			.find(|date| {
+				(mjd_from_gregorian_date(*date) - mjd_from_gregorian_date_native(*date)).abs()
+					> 1e-9
+			});
+		assert_eq!(disagreement, None);
+
+		assert_eq!(mjd_from_gregorian_date_native(date(2000, 1, 1)), 36_524.5);
+		// Leap days in 2000 and 2024 but not in 1900 or 2100, and the turn of a month and a year.
+		for (before, after, days) in [
+			(date(2000, 2, 28), date(2000, 3, 1), 2.0),
+			(date(2024, 2, 28), date(2024, 3, 1), 2.0),
+			(date(1900, 2, 28), date(1900, 3, 1), 1.0),
+			(date(2100, 2, 28), date(2100, 3, 1), 1.0),
+			(date(2023, 4, 30), date(2023, 5, 1), 1.0),
+			(date(2024, 12, 31), date(2025, 1, 1), 1.0),
+		] {
+			for mjd in [mjd_from_gregorian_date, mjd_from_gregorian_date_native] {
+				assert_eq!(mjd(after) - mjd(before), days, "{before} to {after}");
+			}
+		}
+	}
+
+	#[test]
+	fn mjd_of_an_instant_counts_the_time_in_days() {
+		// libastro's epoch is 1899 December 31 12h UT, so J2000.0 falls on a whole day.
+		let noon = at((2000, 1, 1), (12, 0, 0));
+		assert_eq!(mjd_from_gregorian_datetime(noon), 36_525.0);
+		assert_eq!(
+			mjd_from_gregorian_datetime(noon),
+			mjd_from_gregorian_date_native(noon.date()) + 0.5
+		);
+		let evening = at((2023, 6, 1), (18, 0, 0));
+		assert_eq!(
+			mjd_from_gregorian_datetime(evening),
+			mjd_from_gregorian_date(evening.date()) + 0.75
+		);
+	}
+
+	#[test]
+	fn bright_stars_are_in_their_constellations() {
+		// libastro's MJD of J2000.0.
+		const J2000_MJD: f64 = 36_525.0;
+		for (right_ascension, declination, abbreviation) in [
+			(2.530, 89.264, "UMi"),
+			(5.919, 7.407, "Ori"),
+			(6.752, -16.716, "CMa"),
+			(12.443, -63.099, "Cru"),
+			(18.616, 38.784, "Lyr"),
+		] {
+			let position = Equatorial {
+				right_ascension,
+				declination,
+			};
+			let found = constellation(position, J2000_MJD).expect("every position has one");
+			assert_eq!(found.abbreviation, abbreviation);
+		}
+	}
+}
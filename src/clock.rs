@@ -0,0 +1,38 @@
+//! Where the current time comes from, so a run can be pinned to a known instant.
+
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// A source of the current time.
+pub trait Clock {
+	fn now(&self) -> DateTime<Utc>;
+}
+
+/// The system's real time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+	fn now(&self) -> DateTime<Utc> {
+		Utc::now()
+	}
+}
+
+/// A clock that never moves.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl Clock for FixedClock {
+	fn now(&self) -> DateTime<Utc> {
+		self.0
+	}
+}
+
+/// The system's time of day on a fixed date, for watching a simulated day.
+#[derive(Clone, Copy, Debug)]
+pub struct FrozenDateClock(pub NaiveDate);
+
+impl Clock for FrozenDateClock {
+	fn now(&self) -> DateTime<Utc> {
+		self.0.and_time(Utc::now().time()).and_utc()
+	}
+}
@@ -0,0 +1,119 @@
+//! The user's configuration file.
+
+use std::{
+	fs, io,
+	path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// A saved observing position in degrees, used when no coordinates are given.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Home {
+	pub latitude: f64,
+	pub longitude: f64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Config {
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub home: Option<Home>,
+}
+
+impl Config {
+	/// `sidtime/config.toml` in the platform's configuration directory.
+	///
+	/// # Errors
+	///
+	/// Fails when the platform has no configuration directory.
+	pub fn path() -> Result<PathBuf> {
+		dirs::config_dir()
+			.map(|dir| dir.join("sidtime").join("config.toml"))
+			.ok_or_else(|| anyhow!("Could not find a configuration directory"))
+	}
+
+	/// Read the configuration file, or return the default configuration if there is none yet.
+	///
+	/// # Errors
+	///
+	/// Fails when the file exists but cannot be read or parsed.
+	pub fn load() -> Result<Self> {
+		Self::load_from(&Self::path()?)
+	}
+
+	/// Like [`Config::load`], reading from `path`.
+	///
+	/// # Errors
+	///
+	/// Fails when the file exists but cannot be read or parsed.
+	pub fn load_from(path: &Path) -> Result<Self> {
+		match fs::read_to_string(path) {
+			Ok(text) => {
+				toml::from_str(&text).with_context(|| format!("Could not parse {}", path.display()))
+			},
+			Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+			Err(e) => Err(e).with_context(|| format!("Could not read {}", path.display())),
+		}
+	}
+
+	/// Write the configuration file, creating its directory if needed.
+	///
+	/// # Errors
+	///
+	/// Fails when the file cannot be written.
+	pub fn save(&self) -> Result<()> {
+		self.save_to(&Self::path()?)
+	}
+
+	/// Like [`Config::save`], writing to `path`.
+	///
+	/// # Errors
+	///
+	/// Fails when the file cannot be written.
+	pub fn save_to(&self, path: &Path) -> Result<()> {
+		if let Some(dir) = path.parent() {
+			fs::create_dir_all(dir)
+				.with_context(|| format!("Could not create {}", dir.display()))?;
+		}
+		fs::write(path, toml::to_string(self)?)
+			.with_context(|| format!("Could not write {}", path.display()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A path in a fresh directory under the system's temporary directory.
+	fn scratch_path(name: &str) -> PathBuf {
+		let dir =
+			std::env::temp_dir().join(format!("sidtime-config-{}-{name}", std::process::id()));
+		let _ = fs::remove_dir_all(&dir);
+		dir.join("nested").join("config.toml")
+	}
+
+	#[test]
+	fn home_round_trips() {
+		let path = scratch_path("round-trip");
+		let config = Config {
+			home: Some(Home {
+				latitude: -33.8568,
+				longitude: 151.2153,
+			}),
+		};
+		config.save_to(&path).expect("saved");
+		assert_eq!(Config::load_from(&path).expect("loaded"), config);
+
+		let cleared = Config { home: None };
+		cleared.save_to(&path).expect("saved");
+		assert_eq!(Config::load_from(&path).expect("loaded"), cleared);
+		let _ = fs::remove_dir_all(path.parent().and_then(Path::parent).expect("scratch dir"));
+	}
+
+	#[test]
+	fn missing_file_is_the_default() {
+		let path = scratch_path("missing");
+		assert_eq!(Config::load_from(&path).expect("loaded"), Config::default());
+	}
+}
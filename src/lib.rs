@@ -0,0 +1,9 @@
+//! The computations behind the `sidtime` clock that are useful outside the binary.
+
+pub mod astro;
+pub mod clock;
+pub mod config;
+pub mod observer;
+pub mod profile;
+pub mod time;
+pub mod timezone;
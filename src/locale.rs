@@ -0,0 +1,248 @@
+//! Translations of the row labels in the text display.
+
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Locale {
+	/// English
+	En,
+	/// German
+	De,
+	/// Spanish
+	Es,
+	/// French
+	Fr,
+}
+
+/// Each English label with its German, Spanish and French translations.
+const LABELS: &[(&str, [&str; 3])] = &[
+	("Zone for", ["Zone für", "Zona para", "Fuseau pour"]),
+	("Local Time", ["Ortszeit", "Hora local", "Heure locale"]),
+	(
+		"Geocentric Latitude",
+		[
+			"Geozentrische Breite",
+			"Latitud geocéntrica",
+			"Latitude géocentrique",
+		],
+	),
+	(
+		"Longitude Correction",
+		[
+			"Längenkorrektur",
+			"Corrección de longitud",
+			"Correction de longitude",
+		],
+	),
+	("Longitude", ["Länge", "Longitud", "Longitude"]),
+	(
+		"Nautical Zone Time",
+		[
+			"Nautische Zonenzeit",
+			"Hora de zona náutica",
+			"Heure du fuseau nautique",
+		],
+	),
+	(
+		"Gregorian Date",
+		[
+			"Gregorianisches Datum",
+			"Fecha gregoriana",
+			"Date grégorienne",
+		],
+	),
+	(
+		"Universal Time",
+		["Weltzeit", "Tiempo universal", "Temps universel"],
+	),
+	(
+		"Modified Julian Day",
+		[
+			"Modifiziertes Julianisches Datum",
+			"Día juliano modificado",
+			"Jour julien modifié",
+		],
+	),
+	(
+		"Greenwich mean Sidereal Time",
+		[
+			"Mittlere Greenwich-Sternzeit",
+			"Tiempo sidéreo medio de Greenwich",
+			"Temps sidéral moyen de Greenwich",
+		],
+	),
+	(
+		"Local mean Sidereal Time",
+		[
+			"Mittlere Ortssternzeit",
+			"Tiempo sidéreo medio local",
+			"Temps sidéral moyen local",
+		],
+	),
+	(
+		"Martian Meridian Sidereal Time",
+		[
+			"Mars-Meridian-Sternzeit",
+			"Tiempo sidéreo meridiano marciano",
+			"Temps sidéral méridien martien",
+		],
+	),
+	(
+		"Martian Local Sidereal Time",
+		[
+			"Lokale Mars-Sternzeit",
+			"Tiempo sidéreo local marciano",
+			"Temps sidéral local martien",
+		],
+	),
+	(
+		"GMST at 0h UT",
+		["GMST um 0h UT", "GMST a las 0h UT", "GMST à 0h UT"],
+	),
+	(
+		"Zenith RA/Dec",
+		["Zenit RA/Dek", "AR/Dec del cenit", "AD/Déc du zénith"],
+	),
+	(
+		"Zenith Constellation",
+		[
+			"Sternbild im Zenit",
+			"Constelación del cenit",
+			"Constellation au zénith",
+		],
+	),
+	(
+		"Sidereal Days This Year",
+		[
+			"Sterntage in diesem Jahr",
+			"Días sidéreos este año",
+			"Jours sidéraux cette année",
+		],
+	),
+	(
+		"Apparent Solar Time",
+		[
+			"Wahre Sonnenzeit",
+			"Tiempo solar verdadero",
+			"Temps solaire vrai",
+		],
+	),
+	(
+		"Solar Hour Angle",
+		[
+			"Stundenwinkel der Sonne",
+			"Ángulo horario del Sol",
+			"Angle horaire du Soleil",
+		],
+	),
+	(
+		"Next Sidereal Midnight",
+		[
+			"Nächste Sternzeit-Mitternacht",
+			"Próxima medianoche sidérea",
+			"Prochain minuit sidéral",
+		],
+	),
+	(
+		"LMST at Solar Noon",
+		[
+			"Ortssternzeit am wahren Mittag",
+			"TSML al mediodía solar",
+			"TSML au midi solaire",
+		],
+	),
+	(
+		"Sunrise",
+		["Sonnenaufgang", "Salida del sol", "Lever du soleil"],
+	),
+	(
+		"Sunset",
+		["Sonnenuntergang", "Puesta del sol", "Coucher du soleil"],
+	),
+	(
+		"Dark Time Remaining",
+		[
+			"Verbleibende Dunkelheit",
+			"Oscuridad restante",
+			"Obscurité restante",
+		],
+	),
+	(
+		"Time Until Spotiswoode Peak Time",
+		[
+			"Zeit bis zur Spotiswoode-Spitze",
+			"Tiempo hasta el pico Spotiswoode",
+			"Temps avant le pic Spotiswoode",
+		],
+	),
+	(
+		"Time Since Spotiswoode Peak Time",
+		[
+			"Zeit seit der Spotiswoode-Spitze",
+			"Tiempo desde el pico Spotiswoode",
+			"Temps depuis le pic Spotiswoode",
+		],
+	),
+	(
+		"Loop Drift",
+		["Schleifendrift", "Deriva del bucle", "Dérive de la boucle"],
+	),
+	("Time Until", ["Zeit bis", "Tiempo hasta", "Temps avant"]),
+	(
+		"Object Altitude",
+		[
+			"Höhe des Objekts",
+			"Altura del objeto",
+			"Hauteur de l'objet",
+		],
+	),
+	(
+		"Object Azimuth",
+		[
+			"Azimut des Objekts",
+			"Acimut del objeto",
+			"Azimut de l'objet",
+		],
+	),
+];
+
+impl Locale {
+	/// The locale named by `LC_ALL`, `LC_MESSAGES` or `LANG`, in that order, falling back to
+	/// English for anything without a translation.
+	pub fn from_env() -> Self {
+		let language = ["LC_ALL", "LC_MESSAGES", "LANG"]
+			.iter()
+			.filter_map(|name| std::env::var(name).ok())
+			.find(|value| !value.is_empty())
+			.unwrap_or_default();
+		// Values look like `de_DE.UTF-8`, and only the language matters for the labels.
+		match language.get(..2) {
+			Some("de") => Self::De,
+			Some("es") => Self::Es,
+			Some("fr") => Self::Fr,
+			_ => Self::En,
+		}
+	}
+
+	/// Translate an English label, leaving it in English when there is no translation.
+	pub fn label(self, english: &'static str) -> &'static str {
+		let index = match self {
+			Self::En => return english,
+			Self::De => 0,
+			Self::Es => 1,
+			Self::Fr => 2,
+		};
+		LABELS
+			.iter()
+			.find(|(label, _)| *label == english)
+			.map_or(english, |(_, translations)| translations[index])
+	}
+
+	/// Swap the decimal point in a formatted number for the locale's separator.
+	pub fn decimal(self, number: String) -> String {
+		match self {
+			Self::En => number,
+			Self::De | Self::Es | Self::Fr => number.replace('.', ","),
+		}
+	}
+}
@@ -1,182 +1,4283 @@
-use std::str::FromStr;
+mod locale;
 
-use anyhow::{anyhow, Result};
-use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc};
+use std::{
+	collections::HashSet,
+	fs::{self, File},
+	io::{BufRead, BufReader, BufWriter, Write},
+	path::{Path, PathBuf},
+	str::FromStr,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+	time::Instant,
+};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{
+	DateTime, Datelike, Duration, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, NaiveTime,
+	Offset, SecondsFormat, TimeZone, Timelike, Utc,
+};
 use chrono_tz::Tz;
-use clap::Parser;
-use libastro_sys::{cal_mjd, utc_gst};
+use clap::{ArgAction, ArgGroup, Parser, Subcommand, ValueEnum};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use locale::Locale;
 use once_cell::sync::Lazy;
-use tzf_rs::DefaultFinder;
+use serde_json::json;
+use sidereal_time_calculator::{
+	astro::{
+		checked_sidereal_time, constellation, geocentric_latitude, greenwich_mean_sidereal_time,
+		julian_date, local_mean_sidereal_time, mjd_from_gregorian_date,
+		mjd_from_gregorian_datetime, sidereal_days_between, trace_ffi, utc_to_float, Atmosphere,
+		Body, Constellation, Equatorial, GmstAlgorithm, J2000,
+	},
+	clock::{Clock, FrozenDateClock, SystemClock},
+	config::{Config, Home},
+	observer::{great_circle_midpoint, DailyCrossing, Darkness, Observer, SIDEREAL_TO_SOLAR},
+	profile,
+	time::{hours_to_duration, normalize_hours},
+	timezone::{get_timezone, nearest_timezone_border, timezone_candidates},
+};
 
-fn utc_to_float(time: NaiveTime) -> f64 {
-	f64::from(time.hour())
-		+ (f64::from(time.minute()) / (60.0))
-		+ (f64::from(time.second()) / (60.0 * 60.0))
-		+ (f64::from(time.nanosecond()) / (60.0 * 60.0 * 1_000_000_000.0))
+/// Format signed decimal hours as `±HH:MM:SS`, rounded to the nearest second.
+#[allow(clippy::cast_possible_truncation)]
+fn format_signed_hours(hours: f64) -> String {
+	let sign = if hours < 0.0 { '-' } else { '+' };
+	let total_seconds = (hours.abs() * 3600.0).round() as i64;
+	format!(
+		"{sign}{:02}:{:02}:{:02}",
+		total_seconds / 3600,
+		total_seconds / 60 % 60,
+		total_seconds % 60
+	)
 }
 
-#[must_use]
-fn mjd_from_gregorian_date(date: NaiveDate) -> f64 {
-	let dy = f64::from(date.day());
-	let mn = i32::try_from(date.month()).unwrap();
-	let yr = date.year();
-	let mut mjd = 0.0;
-	unsafe { cal_mjd(mn, dy, yr, std::ptr::addr_of_mut!(mjd)) };
-	mjd
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum AngleFormat {
+	/// Degrees, arcminutes and arcseconds, e.g. `-12° 30′ 15″`
+	Sexagesimal,
+	/// Decimal degrees, e.g. `-12.50°`
+	Decimal,
 }
 
-#[must_use]
-fn mjd_from_gregorian_datetime(datetime: NaiveDateTime) -> f64 {
-	let mjd = mjd_from_gregorian_date(datetime.date());
-	mjd + utc_to_float(datetime.time())
+/// Format an angle in degrees, with a single leading sign for negative angles.
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_sign_loss)]
+fn format_angle(degrees: f64, format: AngleFormat) -> String {
+	match format {
+		AngleFormat::Decimal => format!("{:.2}°", degrees),
+		AngleFormat::Sexagesimal => {
+			// Round once on the total so 59.6″ carries into the minutes instead of showing 60″.
+			let total_arcseconds = (degrees.abs() * 3600.0).round() as u64;
+			let sign = if degrees < 0.0 && total_arcseconds > 0 {
+				"-"
+			} else {
+				""
+			};
+			format!(
+				"{sign}{}° {:02}′ {:02}″",
+				total_arcseconds / 3600,
+				total_arcseconds / 60 % 60,
+				total_arcseconds % 60
+			)
+		},
+	}
 }
 
-#[must_use]
-fn greenwich_mean_sidereal_time(datetime: NaiveDateTime) -> f64 {
-	let mut gst = 0.0;
-	let utc = utc_to_float(datetime.time());
-	let mjd = mjd_from_gregorian_date(datetime.date()).floor();
-	unsafe { utc_gst(mjd, utc, std::ptr::addr_of_mut!(gst)) };
-	gst
+/// The nautical timezone offset in whole hours, from 15° wide zones centred on multiples of 15°.
+///
+/// Longitudes exactly on a boundary, such as 7.5°, round away from Greenwich.
+#[allow(clippy::cast_possible_truncation)]
+fn nautical_offset_hours(longitude: f64) -> i32 {
+	(longitude / 15.0).round() as i32
 }
 
-/// Find the timezone for the given coordinates
-fn get_timezone(latitude: f64, longitude: f64) -> Result<Tz> {
-	let finder = DefaultFinder::new();
-	let timezone = finder.get_tz_names(longitude, latitude);
-	let tz_str = match timezone.len() {
-		0 => Err(anyhow!("No timezones found")),
-		1 => Ok(timezone.first().expect("already checked").to_owned()),
-		_ => Err(anyhow!("Todo: Allow picking a timezone name")),
-	}?;
-	Tz::from_str(tz_str).map_err(|e| anyhow!("Could not convert string: {e}"))
+/// The meridian in degrees whose mean solar time matches the zone's current UTC offset.
+fn zone_meridian(utc: DateTime<Utc>, timezone: Tz) -> f64 {
+	let offset_seconds = utc
+		.with_timezone(&timezone)
+		.offset()
+		.fix()
+		.local_minus_utc();
+	f64::from(offset_seconds) / 3600.0 * 15.0
 }
 
 #[allow(clippy::cast_possible_truncation)]
-#[allow(clippy::cast_sign_loss)]
 fn decimal_to_time(dec_time: f64) -> Result<NaiveTime> {
-	assert!(dec_time.is_sign_positive());
-	let hr = dec_time;
-	let min = hr.fract() * 60.0;
-	let sec = min.fract() * 60.0;
-	let ns = sec.fract() * 1_000_000_000.0;
+	const NANOS_PER_SECOND: i64 = 1_000_000_000;
+	const NANOS_PER_HOUR: i64 = 3_600 * NANOS_PER_SECOND;
+	// The cast below would silently saturate anything outside a single day. Exactly 24 hours can
+	// come out of rounding a duration and carries to midnight like 23:59:60 does.
+	if !(0.0..=24.0).contains(&dec_time) {
+		return Err(anyhow!("Time out of range, time: {dec_time}"));
+	}
+	// Scaling once and splitting the whole nanoseconds with integers keeps the rounding error to
+	// a single step, where taking the fraction of each unit in turn compounds it. Rounding up to
+	// a whole day lands on midnight of the next day.
+	let nanos = ((dec_time * NANOS_PER_HOUR as f64).round() as i64).rem_euclid(24 * NANOS_PER_HOUR);
+	let seconds = nanos / NANOS_PER_SECOND;
+	NaiveTime::from_num_seconds_from_midnight_opt(
+		u32::try_from(seconds)?,
+		u32::try_from(nanos % NANOS_PER_SECOND)?,
+	)
+	.ok_or_else(|| anyhow!("Time conversion failed, time: {dec_time}"))
+}
+
+const TIME_FMT_STRING: &str = "%T.%6f";
+
+/// Digits after the decimal point for each row of the text display.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct RowPrecision {
+	utc: usize,
+	local: usize,
+	/// `None` prints the MJD with as many digits as it takes to round-trip.
+	mjd: Option<usize>,
+	gmst: usize,
+	lmst: usize,
+	gmst_at_0h: usize,
+	solar: usize,
+	/// The latitude and longitude in the first row.
+	coords: usize,
+}
+
+impl Default for RowPrecision {
+	fn default() -> Self {
+		Self {
+			utc: 6,
+			local: 6,
+			mjd: None,
+			gmst: 6,
+			lmst: 6,
+			gmst_at_0h: 6,
+			solar: 6,
+			coords: 1,
+		}
+	}
+}
 
-	NaiveTime::from_hms_nano_opt(hr as u32, min as u32, sec as u32, ns as u32)
-		.ok_or_else(|| anyhow!("Time conversion failed, time: {dec_time}"))
+/// Parse `row=digits` pairs separated by commas, e.g. `lmst=0,mjd=6`, keeping the default for
+/// every row left out.
+fn parse_row_precision(value: &str) -> std::result::Result<RowPrecision, String> {
+	let mut precision = RowPrecision::default();
+	for pair in value.split(',').filter(|pair| !pair.trim().is_empty()) {
+		let (row, digits) = pair
+			.split_once('=')
+			.ok_or_else(|| format!("Expected row=digits, got {pair:?}"))?;
+		let digits: usize = digits
+			.trim()
+			.parse()
+			.map_err(|e| format!("Bad digit count for {row}: {e}"))?;
+		if digits > 9 {
+			return Err(format!("{row} can have at most 9 digits, got {digits}"));
+		}
+		match row.trim() {
+			"utc" => precision.utc = digits,
+			"local" => precision.local = digits,
+			"mjd" => precision.mjd = Some(digits),
+			"gmst" => precision.gmst = digits,
+			"lmst" => precision.lmst = digits,
+			"gmst0h" => precision.gmst_at_0h = digits,
+			"solar" => precision.solar = digits,
+			"coords" => precision.coords = digits,
+			row => {
+				return Err(format!(
+					"Unknown row {row:?}, expected one of utc, local, mjd, gmst, lmst, gmst0h, \
+					 solar, coords"
+				))
+			},
+		}
+	}
+	Ok(precision)
 }
 
-fn local_mean_sidereal_time(gmst: f64, longitude: f64) -> f64 {
-	24.0 * ((gmst + longitude / 15.0) / 24.0).fract()
+/// Format a time of day as `HH:MM:SS` with up to nine digits of fractional seconds, truncated
+/// like chrono's `%6f`.
+fn format_time_of_day<T: Timelike>(time: &T, digits: usize) -> String {
+	let mut text = format!(
+		"{:02}:{:02}:{:02}",
+		time.hour(),
+		time.minute(),
+		time.second()
+	);
+	if digits > 0 {
+		// A leap second keeps counting nanoseconds past a billion, so hold it at the end of 59.
+		let nanos = format!("{:09}", time.nanosecond().min(999_999_999));
+		text.push('.');
+		text.push_str(&nanos[..digits.min(9)]);
+	}
+	text
 }
 
-fn display_info(latitude: Option<f64>, longitude: f64) -> Result<()> {
-	const TIME_FMT_STRING: &str = "%T.%6f";
-	const TIME_ZONE_FMT_STRING: &str = "%T.%6f %z/%Z";
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum TimeNotation {
+	/// `14:32:07`
+	Colon,
+	/// `14h 32m 07s`, as sidereal times and right ascensions are often written
+	Hms,
+}
 
-	let term = console::Term::buffered_stdout();
+/// Round decimal hours to the nearest `digits` decimals of a second, wrapped into a single day so
+/// a result of 24 hours becomes 0. Rounding first keeps a value a hair under 24 from showing as
+/// `23:59:59.999999` when it is closer to midnight.
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+fn round_hours(hours: f64, digits: usize) -> f64 {
+	let scale = 10_f64.powi(digits as i32);
+	normalize_hours((hours * 3600.0 * scale).round() / scale / 3600.0)
+}
+
+/// Format a sidereal time or right ascension in decimal hours in the chosen notation, rounded to
+/// `digits` decimals on the seconds.
+fn format_sidereal_time(hours: f64, digits: usize, notation: TimeNotation) -> Result<String> {
+	let text = format_time_of_day(&decimal_to_time(round_hours(hours, digits))?, digits);
+	Ok(match notation {
+		TimeNotation::Colon => text,
+		TimeNotation::Hms => {
+			let mut parts = text.splitn(3, ':');
+			let (hours, minutes, seconds) = (
+				parts.next().expect("hours"),
+				parts.next().expect("minutes"),
+				parts.next().expect("seconds"),
+			);
+			format!("{hours}h {minutes}m {seconds}s")
+		},
+	})
+}
+
+/// Format an instant as `HH:MM:SS.f… +zzzz/ZONE`.
+fn format_zoned_time<Z: TimeZone>(time: &DateTime<Z>, digits: usize) -> String
+where
+	Z::Offset: std::fmt::Display,
+{
+	format!(
+		"{} {}",
+		format_time_of_day(time, digits),
+		time.format("%z/%Z")
+	)
+}
+
+static SPOTISWOODE_PEAK_TIME: Lazy<NaiveTime> =
+	Lazy::new(|| NaiveTime::from_hms_opt(13, 30, 0).unwrap());
+
+/// The inputs to a report that stay the same from one refresh to the next.
+struct ReportSettings {
+	observer: Observer,
+	object: Option<Equatorial>,
+	/// Whether to count down to the Spotiswoode peak.
+	spotiswoode_peak: bool,
+	/// Whether to show the geocentric latitude next to the geodetic one.
+	geocentric_latitude: bool,
+	/// More sidereal times to count down to, from `--events-file`.
+	events: Vec<SiderealEvent>,
+	/// More longitudes to show the local sidereal time of, from `--longitudes-file`.
+	sites: Vec<Site>,
+}
+
+/// A labelled longitude to show the local sidereal time of alongside the observer's.
+#[derive(Clone, Debug)]
+struct Site {
+	label: String,
+	/// Degrees east.
+	longitude: f64,
+}
+
+/// Read sites from lines of `longitude[,label]`, skipping blank lines and `#` comments. A site
+/// without a label is labelled with its longitude. Sites are kept in file order unless `sort` asks
+/// for them from west to east.
+fn load_sites(path: &Path, sort: bool) -> Result<Vec<Site>> {
+	let text =
+		fs::read_to_string(path).with_context(|| format!("Could not read {}", path.display()))?;
+	let mut sites = Vec::new();
+	for (index, line) in text.lines().enumerate() {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+		let error = |message: &str| anyhow!("{}:{}: {message}", path.display(), index + 1);
+		let (longitude, label) = match line.split_once(',') {
+			Some((longitude, label)) => (longitude.trim(), label.trim()),
+			None => (line, line),
+		};
+		let longitude: f64 = longitude
+			.parse()
+			.map_err(|e| error(&format!("Bad longitude {longitude:?}: {e}")))?;
+		if !longitude.is_finite() {
+			return Err(error(&format!("Bad longitude {longitude}")));
+		}
+		if label.is_empty() {
+			return Err(error("Missing site label after the comma"));
+		}
+		sites.push(Site {
+			label: label.to_owned(),
+			longitude,
+		});
+	}
+	if sort {
+		sites.sort_by(|a, b| a.longitude.total_cmp(&b.longitude));
+	}
+	Ok(sites)
+}
+
+/// A named local sidereal time to count down to.
+#[derive(Clone, Debug)]
+struct SiderealEvent {
+	name: String,
+	/// Local sidereal time in hours.
+	lst: f64,
+}
+
+/// Read events from lines of `name,HH:MM[:SS]`, skipping blank lines and `#` comments.
+fn load_events(path: &Path) -> Result<Vec<SiderealEvent>> {
+	let text =
+		fs::read_to_string(path).with_context(|| format!("Could not read {}", path.display()))?;
+	let mut events = Vec::new();
+	for (index, line) in text.lines().enumerate() {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+		let error = |message: &str| anyhow!("{}:{}: {message}", path.display(), index + 1);
+		let (name, time) = line
+			.rsplit_once(',')
+			.ok_or_else(|| error("Expected name,HH:MM:SS"))?;
+		let time = NaiveTime::parse_from_str(time.trim(), "%H:%M:%S")
+			.or_else(|_| NaiveTime::parse_from_str(time.trim(), "%H:%M"))
+			.map_err(|e| error(&format!("Bad sidereal time {:?}: {e}", time.trim())))?;
+		let name = name.trim();
+		if name.is_empty() {
+			return Err(error("Missing event name"));
+		}
+		events.push(SiderealEvent {
+			name: name.to_owned(),
+			lst: utc_to_float(time),
+		});
+	}
+	Ok(events)
+}
+
+/// Sidereal time until a daily event at the given local sidereal time, in `[0, 24h)`.
+fn countdown_to(lst: f64, local_mst: f64) -> Duration {
+	// Normalizing the difference gives the time until the next occurrence, never the last.
+	hours_to_duration(normalize_hours(lst - local_mst))
+}
+
+/// Sidereal time to and from the Spotiswoode peak at 13:30 local sidereal time.
+#[derive(Clone, Copy, Debug)]
+struct PeakCountdown {
+	until: Duration,
+	since: Duration,
+}
+
+impl PeakCountdown {
+	fn new(local_mst: f64) -> Self {
+		let peak = utc_to_float(*SPOTISWOODE_PEAK_TIME);
+		Self {
+			until: countdown_to(peak, local_mst),
+			since: countdown_to(local_mst, peak),
+		}
+	}
+}
+
+/// Everything shown for a single instant, computed once so each output format renders the same
+/// values.
+#[derive(Clone, Debug)]
+struct SiderealReport {
+	utc: DateTime<Utc>,
+	latitude: Option<f64>,
+	longitude: f64,
+	timezone: Option<Tz>,
+	body: Body,
+	mjd: f64,
+	/// Sidereal time at the body's prime meridian, which is Greenwich for the Earth.
+	greenwich_mst: f64,
+	local_mst: f64,
+	/// GMST at 00:00:00 UT of the current date, only computed for the Earth.
+	gmst_at_0h: Option<f64>,
+	/// Only computed for the Earth when the latitude is known and `--geocentric-lat` is given.
+	geocentric_latitude: Option<f64>,
+	/// The point overhead, only computed for the Earth when the latitude is known.
+	zenith: Option<Equatorial>,
+	/// The constellation overhead, when the zenith is known.
+	zenith_constellation: Option<Constellation>,
+	/// Sidereal days since 00:00:00 UT on January 1, only computed for the Earth.
+	sidereal_day_of_year: Option<f64>,
+	/// Only computed for the Earth.
+	apparent_solar_time: Option<f64>,
+	/// Hours from local apparent noon in `[-12, 12)`, negative before noon. Only computed for the
+	/// Earth.
+	solar_hour_angle: Option<f64>,
+	/// Local sidereal time at the day's solar transit, only computed for the Earth when the
+	/// latitude is known.
+	lmst_at_solar_noon: Option<f64>,
+	/// The next time the local sidereal time is 0h, only computed for the Earth.
+	sidereal_midnight: Option<DateTime<Utc>>,
+	/// Sunrise and sunset on the observer's local day, only computed for the Earth when the
+	/// latitude is known.
+	sun_rise_set: Option<DailyCrossing>,
+	/// Left out with `--no-peak`.
+	peak: Option<PeakCountdown>,
+	/// Time until each event from `--events-file`, soonest first.
+	events: Vec<(String, Duration)>,
+	/// Local sidereal time at each site from `--longitudes-file`.
+	sites: Vec<(String, f64)>,
+	/// Only computed for the Earth when the latitude is known.
+	darkness: Option<Darkness>,
+	/// Hours by which local mean solar time runs ahead of the civil zone, with the zone's meridian
+	/// in degrees.
+	longitude_correction: Option<(f64, f64)>,
+	/// Altitude and azimuth of the tracked object, when there is one and the latitude is known.
+	object_horizontal: Option<(f64, f64)>,
+}
+
+impl SiderealReport {
+	fn new(utc: DateTime<Utc>, settings: &ReportSettings, timezone: Option<Tz>) -> Result<Self> {
+		let ReportSettings {
+			observer, object, ..
+		} = *settings;
+		let Observer {
+			latitude,
+			longitude,
+			body,
+			..
+		} = observer;
+		let mjd = mjd_from_gregorian_datetime(utc.naive_utc());
+		let greenwich_mst = checked_sidereal_time(observer.gmst(utc), utc.naive_utc())?;
+		let local_mst = checked_sidereal_time(observer.lmst(utc), utc.naive_utc())?;
+		let gmst_at_0h = (body == Body::Earth).then(|| {
+			let midnight = utc
+				.date_naive()
+				.and_hms_opt(0, 0, 0)
+				.expect("midnight exists");
+			observer.gmst(midnight.and_utc())
+		});
+		let zenith = observer.zenith(utc);
+		// The zenith is in coordinates of date, so its equinox is now.
+		let zenith_constellation = zenith.and_then(|zenith| constellation(zenith, mjd));
+		let geocentric_latitude = latitude
+			.filter(|_| settings.geocentric_latitude && body == Body::Earth)
+			.map(geocentric_latitude);
+		let sidereal_day_of_year = (body == Body::Earth).then(|| {
+			let new_year = NaiveDate::from_ymd_opt(utc.year(), 1, 1)
+				.and_then(|date| date.and_hms_opt(0, 0, 0))
+				.expect("January 1 exists");
+			sidereal_days_between(new_year, utc.naive_utc())
+		});
+		let apparent_solar_time = observer.apparent_solar_time(utc);
+		let solar_hour_angle = observer.solar_hour_angle(utc);
+		let lmst_at_solar_noon = observer.lmst_at_solar_noon(utc);
+		let sidereal_midnight = observer.next_local_sidereal_time(utc, 0.0);
+		// The local mean solar date, as for the LMST at solar noon.
+		let sun_rise_set =
+			observer.sun_rise_set((utc + hours_to_duration(longitude / 15.0)).date_naive());
+		let darkness = observer.darkness(utc);
+		let mut events = settings
+			.events
+			.iter()
+			.map(|event| (event.name.clone(), countdown_to(event.lst, local_mst)))
+			.collect::<Vec<_>>();
+		events.sort_by_key(|(_, until)| *until);
+		let sites = settings
+			.sites
+			.iter()
+			.map(|site| {
+				let lmst = local_mean_sidereal_time(greenwich_mst, site.longitude);
+				(site.label.clone(), lmst)
+			})
+			.collect();
+
+		let longitude_correction = timezone.map(|timezone| {
+			let meridian = zone_meridian(utc, timezone);
+			((longitude - meridian) / 15.0, meridian)
+		});
+
+		let peak = settings
+			.spotiswoode_peak
+			.then(|| PeakCountdown::new(local_mst));
+
+		let object_horizontal = object.and_then(|object| observer.horizontal(utc, object));
+
+		Ok(Self {
+			utc,
+			latitude,
+			longitude,
+			timezone,
+			body,
+			mjd,
+			greenwich_mst,
+			local_mst,
+			gmst_at_0h,
+			geocentric_latitude,
+			zenith,
+			zenith_constellation,
+			sidereal_day_of_year,
+			apparent_solar_time,
+			solar_hour_angle,
+			lmst_at_solar_noon,
+			sidereal_midnight,
+			sun_rise_set,
+			peak,
+			events,
+			sites,
+			darkness,
+			longitude_correction,
+			object_horizontal,
+		})
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum CountdownStyle {
+	/// Always count forward to the next occurrence, e.g. `23:59:00.000000`
+	Wrap,
+	/// Count to the nearest occurrence, negative once it has passed, e.g. `-00:01:00.000000`
+	Signed,
+}
 
-	let timezone;
+/// Format a duration as `HH:MM:SS.ffffff`, ignoring its sign and truncating to the microsecond.
+fn format_duration(duration: Duration) -> String {
+	// Whole seconds can't overflow like whole nanoseconds can, and the remainder is under a second.
+	let seconds = duration.num_seconds().abs();
+	let micros = (duration - Duration::seconds(duration.num_seconds()))
+		.num_microseconds()
+		.expect("less than a second")
+		.abs();
+	format!(
+		"{:02}:{:02}:{:02}.{:06}",
+		seconds / 3600,
+		seconds / 60 % 60,
+		seconds % 60,
+		micros
+	)
+}
 
-	if let Some(latitude) = latitude {
-		timezone = get_timezone(latitude, longitude).ok();
+/// Format a duration as `±HH:MM:SS.ffffff`.
+fn format_signed_duration(duration: Duration) -> String {
+	let sign = if duration < Duration::zero() {
+		'-'
 	} else {
-		timezone = None
+		'+'
+	};
+	format!("{sign}{}", format_duration(duration))
+}
+
+/// Format the time left until a daily event, given as a duration in `[0, 24h]`.
+fn format_countdown(duration: Duration, style: CountdownStyle) -> String {
+	match style {
+		// Rounding can land exactly on a full day, which is the event happening now.
+		CountdownStyle::Wrap if duration >= Duration::days(1) => {
+			format_duration(duration - Duration::days(1))
+		},
+		CountdownStyle::Wrap => format_duration(duration),
+		CountdownStyle::Signed if duration > Duration::hours(12) => {
+			format_signed_duration(duration - Duration::hours(24))
+		},
+		CountdownStyle::Signed => format_signed_duration(duration),
 	}
+}
 
-	loop {
-		let utc_datetime = Utc::now();
+/// Colour a countdown on a gradient from red a day away, through yellow, to green once it is
+/// within `imminent`.
+#[allow(
+	clippy::cast_precision_loss,
+	clippy::cast_possible_truncation,
+	clippy::cast_sign_loss
+)]
+fn peak_gradient(
+	text: String,
+	until: Duration,
+	imminent: Duration,
+) -> console::StyledObject<String> {
+	let far = (Duration::days(1) - imminent).num_seconds().max(1) as f64;
+	let left = (until - imminent).num_seconds().max(0) as f64;
+	// 0 is imminent and 1 is a whole day away.
+	let distance = (left / far).min(1.0);
+	// Steps of the 6×6×6 colour cube in the 256 colour palette: green rises from red to yellow,
+	// then red falls from yellow to green.
+	let steps = ((1.0 - distance) * 10.0).round() as u8;
+	let (red, green) = if steps <= 5 {
+		(5, steps)
+	} else {
+		(10 - steps, 5)
+	};
+	console::style(text).color256(16 + 36 * red + 6 * green)
+}
+
+/// Show a countdown in reverse video once it is within --notify-within.
+fn highlight_within(
+	text: console::StyledObject<String>,
+	until: Duration,
+	within: Option<Duration>,
+) -> console::StyledObject<String> {
+	match within {
+		Some(within) if until <= within => text.reverse(),
+		_ => text,
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Align {
+	/// Start every value right after its label
+	Left,
+	/// Line up the ends of the values
+	Right,
+	/// Line up the first decimal point of each value, or its end when it has none
+	Decimal,
+}
+
+/// Pad the values of `label: value` rows so they line up as asked.
+fn align_values(info: &str, align: Align) -> String {
+	if align == Align::Left {
+		return info.to_owned();
+	}
+	let rows = info
+		.lines()
+		.map(|line| line.split_once(": "))
+		.collect::<Vec<_>>();
+	// Colored values carry escape codes that take no room on screen.
+	let width = |value: &str| console::measure_text_width(value);
+	let anchor = |value: &str| {
+		let plain = console::strip_ansi_codes(value);
+		match align {
+			Align::Decimal => plain
+				.find('.')
+				.map_or_else(|| width(&plain), |dot| width(&plain[..dot])),
+			Align::Left | Align::Right => width(&plain),
+		}
+	};
+	let widest = rows
+		.iter()
+		.flatten()
+		.map(|(_, value)| anchor(value))
+		.max()
+		.unwrap_or(0);
+	info.lines()
+		.zip(rows)
+		.map(|(line, row)| match row {
+			Some((label, value)) => {
+				format!("{label}: {}{value}", " ".repeat(widest - anchor(value)))
+			},
+			None => line.to_owned(),
+		})
+		.collect::<Vec<_>>()
+		.join("\n")
+}
+
+fn render_info(report: &SiderealReport, options: &DisplayOptions) -> Result<String> {
+	let mut info = String::new();
+	let locale = options.locale;
+	let precision = options.precision;
+
+	let zone_for_position = report.latitude.zip(report.timezone);
+	if let Some((latitude, timezone)) = zone_for_position {
+		info.push_str(&format!(
+			"{:>32}: {}\n",
+			format!(
+				"{} {}, {}",
+				locale.label("Zone for"),
+				locale.decimal(format!("{:>5.*}", precision.coords, latitude)),
+				locale.decimal(format!("{:>5.*}", precision.coords, report.longitude))
+			),
+			timezone.name()
+		));
+	}
+
+	// The zone can be known without a latitude when it is given with --assume-tz.
+	if let Some(timezone) = civil_timezone(report, options) {
+		info.push_str(&format!(
+			"{:>32}: {}\n",
+			locale.label("Local Time"),
+			format_zoned_time(&report.utc.with_timezone(&timezone), precision.local)
+		));
+	} else if report.latitude.is_some() && !options.utc_only {
+		// With a latitude the zone would normally be known, so say why the civil time is missing.
+		info.push_str(&format!(
+			"{:>32}: (tz unavailable)\n",
+			locale.label("Local Time")
+		));
+	}
+
+	if let Some((correction, meridian)) = report.longitude_correction {
+		info.push_str(&format!(
+			"{:>32}: {} (zone meridian {})\n",
+			locale.label("Longitude Correction"),
+			format_signed_hours(correction),
+			format_angle(meridian, options.angle_format)
+		));
+	}
+
+	if zone_for_position.is_none() {
+		info.push_str(&format!(
+			"{:>32}: {}\n",
+			locale.label("Longitude"),
+			locale.decimal(format!("{:>5.*}", precision.coords, report.longitude))
+		));
+	}
+
+	if options.nautical_zone {
+		let offset = nautical_offset_hours(report.longitude);
+		let zone = FixedOffset::east_opt(offset * 3600).expect("nautical offsets are within a day");
+		info.push_str(&format!(
+			"{:>32}: {} (zone {:+})\n",
+			locale.label("Nautical Zone Time"),
+			format_zoned_time(&report.utc.with_timezone(&zone), precision.local),
+			offset
+		));
+	}
+
+	info.push_str(&format!(
+		"{:>32}: {}\n",
+		locale.label("Gregorian Date"),
+		report.utc.date_naive()
+	));
+
+	if let (Some(geodetic), Some(geocentric)) = (report.latitude, report.geocentric_latitude) {
+		info.push_str(&format!(
+			"{:>32}: {}° (geodetic {}°)\n",
+			locale.label("Geocentric Latitude"),
+			locale.decimal(format!("{geocentric:.6}")),
+			locale.decimal(format!("{geodetic:.6}"))
+		));
+	}
+
+	info.push_str(&format!(
+		"{:>32}: {}\n",
+		locale.label("Universal Time"),
+		format_zoned_time(&report.utc, precision.utc)
+	));
 
-		let mut info = String::new();
+	info.push_str(&format!(
+		"{:>32}: {}\n",
+		locale.label("Modified Julian Day"),
+		match precision.mjd {
+			Some(digits) => format!("{:.*}", digits, report.mjd),
+			None => report.mjd.to_string(),
+		}
+	));
 
-		if let (Some(latitude), Some(timezone)) = (latitude, timezone) {
+	match report.body {
+		Body::Earth => {
 			info.push_str(&format!(
-				"           Zone for {:>5.1}, {:>5.1}: {:?}\n",
-				latitude, longitude, timezone
+				"{:>32}: {}\n",
+				locale.label("Greenwich mean Sidereal Time"),
+				format_sidereal_time(report.greenwich_mst, precision.gmst, options.time_notation)?
 			));
 
-			let local_time = utc_datetime.with_timezone(&timezone);
 			info.push_str(&format!(
-				"                      Local Time: {}\n",
-				local_time.format(TIME_ZONE_FMT_STRING)
+				"{:>32}: {}\n",
+				locale.label("Local mean Sidereal Time"),
+				format_sidereal_time(report.local_mst, precision.lmst, options.time_notation)?
 			));
-		} else {
+		},
+		Body::Mars => {
 			info.push_str(&format!(
-				"                       Longitude: {:>5.1}\n",
-				longitude
-			))
-		}
+				"{:>32}: {}\n",
+				locale.label("Martian Meridian Sidereal Time"),
+				format_sidereal_time(report.greenwich_mst, precision.gmst, options.time_notation)?
+			));
+
+			info.push_str(&format!(
+				"{:>32}: {}\n",
+				locale.label("Martian Local Sidereal Time"),
+				format_sidereal_time(report.local_mst, precision.lmst, options.time_notation)?
+			));
+		},
+	}
+
+	for (label, lmst) in &report.sites {
+		info.push_str(&format!(
+			"{:>32}: {}\n",
+			format!("LMST {label}"),
+			format_sidereal_time(*lmst, precision.lmst, options.time_notation)?
+		));
+	}
+
+	if let (true, Some(gmst_at_0h)) = (options.gmst_at_0h, report.gmst_at_0h) {
+		info.push_str(&format!(
+			"{:>32}: {}\n",
+			locale.label("GMST at 0h UT"),
+			format_sidereal_time(gmst_at_0h, precision.gmst_at_0h, options.time_notation)?
+		));
+	}
+
+	if let Some(zenith) = report.zenith {
+		info.push_str(&format!(
+			"{:>32}: {} / {}\n",
+			locale.label("Zenith RA/Dec"),
+			format_sidereal_time(
+				zenith.right_ascension,
+				precision.lmst,
+				options.time_notation
+			)?,
+			format_angle(zenith.declination, options.angle_format)
+		));
+	}
+
+	if let Some(constellation) = &report.zenith_constellation {
+		info.push_str(&format!(
+			"{:>32}: {} ({})\n",
+			locale.label("Zenith Constellation"),
+			constellation.name,
+			constellation.abbreviation
+		));
+	}
 
-		let curr_date = utc_datetime.date_naive();
+	if let Some(sidereal_day) = report.sidereal_day_of_year {
+		info.push_str(&format!(
+			"{:>32}: {:.6}\n",
+			locale.label("Sidereal Days This Year"),
+			sidereal_day
+		));
+	}
 
+	if let Some(apparent_solar_time) = report.apparent_solar_time {
 		info.push_str(&format!(
-			"                  Gregorian Date: {}\n",
-			curr_date
+			"{:>32}: {}\n",
+			locale.label("Apparent Solar Time"),
+			format_time_of_day(&decimal_to_time(apparent_solar_time)?, precision.solar)
 		));
+	}
 
+	if let Some(solar_hour_angle) = report.solar_hour_angle {
 		info.push_str(&format!(
-			"                  Universal Time: {}\n",
-			utc_datetime.format(TIME_ZONE_FMT_STRING)
+			"{:>32}: {}\n",
+			locale.label("Solar Hour Angle"),
+			format_signed_hours(solar_hour_angle)
 		));
+	}
 
-		let mjd = mjd_from_gregorian_datetime(utc_datetime.naive_utc());
-		info.push_str(&format!("             Modified Julian Day: {}\n", mjd));
+	// Events are shown in UTC, followed by the civil time when there is a zone to show it in.
+	let civil_time = |at: DateTime<Utc>| {
+		let mut text = format_zoned_time(&at, 0);
+		if let Some(timezone) = civil_timezone(report, options) {
+			text.push_str(&format!(
+				" ({})",
+				format_zoned_time(&at.with_timezone(&timezone), 0)
+			));
+		}
+		text
+	};
 
-		let greenwich_mst = greenwich_mean_sidereal_time(utc_datetime.naive_utc());
+	if let Some(midnight) = report.sidereal_midnight {
 		info.push_str(&format!(
-			"    Greenwich mean Sidereal Time: {} \n",
-			decimal_to_time(greenwich_mst)?.format(TIME_FMT_STRING)
+			"{:>32}: {}\n",
+			locale.label("Next Sidereal Midnight"),
+			civil_time(midnight)
 		));
+	}
 
-		let local_mst = local_mean_sidereal_time(greenwich_mst, longitude);
+	if let Some(lmst) = report.lmst_at_solar_noon {
 		info.push_str(&format!(
-			"        Local mean Sidereal Time: {}\n",
-			decimal_to_time(local_mst)?.format(TIME_FMT_STRING)
+			"{:>32}: {}\n",
+			locale.label("LMST at Solar Noon"),
+			format_sidereal_time(lmst, precision.lmst, options.time_notation)?
 		));
+	}
 
-		let time_until_peak = {
-			static SPOTISWOODE_PEAK_TIME: Lazy<NaiveTime> =
-				Lazy::new(|| NaiveTime::from_hms_opt(13, 30, 0).unwrap());
+	match report.sun_rise_set {
+		Some(DailyCrossing::RisesAndSets { rise, set }) => {
+			info.push_str(&format!(
+				"{:>32}: {}\n",
+				locale.label("Sunrise"),
+				civil_time(rise)
+			));
+			info.push_str(&format!(
+				"{:>32}: {}\n",
+				locale.label("Sunset"),
+				civil_time(set)
+			));
+		},
+		Some(DailyCrossing::AlwaysAbove) => {
+			info.push_str(&format!(
+				"{:>32}: none, the Sun is up all day\n",
+				locale.label("Sunrise")
+			));
+		},
+		Some(DailyCrossing::AlwaysBelow) => {
+			info.push_str(&format!(
+				"{:>32}: none, the Sun is down all day\n",
+				locale.label("Sunrise")
+			));
+		},
+		None => {},
+	}
 
-			let duration = SPOTISWOODE_PEAK_TIME.signed_duration_since(decimal_to_time(local_mst)?);
-			if duration.lt(&Duration::zero()) {
-				// If the duration is negative, add 24 hours to it to get the time until the next occurrence.
-				duration + chrono::Duration::hours(24)
-			} else {
-				duration
+	if let Some(darkness) = report.darkness {
+		info.push_str(&format!(
+			"{:>32}: {}\n",
+			locale.label("Dark Time Remaining"),
+			match darkness {
+				Darkness::Dark(left) => format!("{} until dawn", format_duration(left)),
+				Darkness::Light(until) => format!("{} until dusk", format_duration(until)),
+				Darkness::AlwaysDark => "dark all day".to_owned(),
+				Darkness::NeverDark => "no astronomical darkness".to_owned(),
 			}
+		));
+	}
+
+	if let Some(peak) = report.peak {
+		let until = format_countdown(peak.until, options.countdown_style);
+		let until = match options.peak_imminent {
+			Some(imminent) => peak_gradient(until, peak.until, imminent),
+			None => console::style(until),
 		};
 		info.push_str(&format!(
-			"Time Until Spotiswoode Peak Time: {}",
-			decimal_to_time(
-				time_until_peak.num_nanoseconds().unwrap() as f64 / 1_000_000_000.0 / 60.0 / 60.0
-			)?
-			.format(TIME_FMT_STRING),
+			"{:>32}: {}\n",
+			locale.label("Time Until Spotiswoode Peak Time"),
+			highlight_within(until, peak.until, options.notify_within),
 		));
 
-		let lines_to_clear = info.chars().into_iter().filter(|c| *c == '\n').count();
+		if options.reverse_peak {
+			info.push_str(&format!(
+				"{:>32}: {}\n",
+				locale.label("Time Since Spotiswoode Peak Time"),
+				format_countdown(peak.since, options.countdown_style),
+			));
+		}
+	}
 
-		term.write_line(&info)?;
-		term.flush()?;
-		term.clear_last_lines(lines_to_clear + 1)?;
-		std::thread::sleep(std::time::Duration::from_micros(200));
+	for (name, until) in &report.events {
+		info.push_str(&format!(
+			"{:>32}: {}\n",
+			format!("{} {name}", locale.label("Time Until")),
+			highlight_within(
+				console::style(format_countdown(*until, options.countdown_style)),
+				*until,
+				options.notify_within
+			)
+		));
+	}
+
+	if let Some((altitude, azimuth)) = report.object_horizontal {
+		let altitude_text = format_angle(altitude, options.angle_format);
+		let altitude_text = if altitude < options.horizon_altitude {
+			console::style(altitude_text).red()
+		} else if altitude < options.high_altitude {
+			console::style(altitude_text).yellow()
+		} else {
+			console::style(altitude_text).green()
+		};
+		info.push_str(&format!(
+			"{:>32}: {}\n",
+			locale.label("Object Altitude"),
+			altitude_text
+		));
+		info.push_str(&format!(
+			"{:>32}: {}\n",
+			locale.label("Object Azimuth"),
+			format_angle(azimuth, options.angle_format)
+		));
 	}
+
+	// Every row ends in a newline, but the display adds its own after the last one.
+	info.truncate(info.trim_end_matches('\n').len());
+	Ok(align_values(&info, options.align))
 }
 
-#[derive(Parser, Debug)]
-#[command(name = "sidtime")]
-/// Prints shows the local sidereal time given a longitude.
-struct Cli {
-	/// Latitude
-	#[arg(long)]
-	lat: Option<f64>,
-	/// Longitude (+ for E - for W)
-	#[arg(long)]
-	lon: f64,
+/// A name upper-cased with everything but ASCII letters and digits replaced by `_`, to keep a
+/// compact key a single token so the line still splits on spaces.
+fn compact_key(name: &str) -> String {
+	name.chars()
+		.map(|c| {
+			if c.is_ascii_alphanumeric() {
+				c.to_ascii_uppercase()
+			} else {
+				'_'
+			}
+		})
+		.collect()
 }
 
-fn main() -> Result<()> {
-	let cli = Cli::parse();
+/// The whole report on one greppable line of `KEY=value` pairs.
+fn render_compact(report: &SiderealReport, options: &DisplayOptions) -> Result<String> {
+	let mut line = format!("UTC={}", report.utc.format("%FT%T.%6fZ"));
+	if let Some(timezone) = civil_timezone(report, options) {
+		line.push_str(&format!(
+			" LOCAL={}",
+			report.utc.with_timezone(&timezone).format("%FT%T.%6f%:z")
+		));
+	}
+	if let Some((correction, _)) = report.longitude_correction {
+		line.push_str(&format!(" LONCORR={}", format_signed_hours(correction)));
+	}
+	if options.nautical_zone {
+		line.push_str(&format!(
+			" NAUTICAL={:+}",
+			nautical_offset_hours(report.longitude)
+		));
+	}
+	if report.body == Body::Mars {
+		line.push_str(" BODY=mars");
+	}
+	line.push_str(&format!(
+		" GMST={} LMST={}",
+		decimal_to_time(report.greenwich_mst)?.format(TIME_FMT_STRING),
+		decimal_to_time(report.local_mst)?.format(TIME_FMT_STRING),
+	));
+	if let (true, Some(gmst_at_0h)) = (options.gmst_at_0h, report.gmst_at_0h) {
+		line.push_str(&format!(
+			" GMST0H={}",
+			decimal_to_time(gmst_at_0h)?.format(TIME_FMT_STRING)
+		));
+	}
+	if let Some(zenith) = report.zenith {
+		line.push_str(&format!(
+			" ZENITH_RA={} ZENITH_DEC={:.6}",
+			decimal_to_time(zenith.right_ascension)?.format(TIME_FMT_STRING),
+			zenith.declination
+		));
+	}
+	if let Some(constellation) = &report.zenith_constellation {
+		line.push_str(&format!(
+			" ZENITH_CONSTELLATION={}",
+			constellation.abbreviation
+		));
+	}
+	if let Some(apparent_solar_time) = report.apparent_solar_time {
+		line.push_str(&format!(
+			" SOLAR={}",
+			decimal_to_time(apparent_solar_time)?.format(TIME_FMT_STRING)
+		));
+	}
+	if let Some(solar_hour_angle) = report.solar_hour_angle {
+		line.push_str(&format!(
+			" SOLAR_HA={}",
+			format_signed_hours(solar_hour_angle)
+		));
+	}
+	if let Some(midnight) = report.sidereal_midnight {
+		line.push_str(&format!(
+			" SIDEREAL_MIDNIGHT={}",
+			midnight.to_rfc3339_opts(SecondsFormat::Secs, true)
+		));
+	}
+	if let Some(lmst) = report.lmst_at_solar_noon {
+		line.push_str(&format!(
+			" NOON_LMST={}",
+			decimal_to_time(lmst)?.format(TIME_FMT_STRING)
+		));
+	}
+	match report.darkness {
+		Some(Darkness::Dark(left)) => {
+			line.push_str(&format!(" DARK_REMAINING={}", format_duration(left)));
+		},
+		Some(Darkness::Light(until)) => {
+			line.push_str(&format!(" DARK_IN={}", format_duration(until)));
+		},
+		Some(Darkness::AlwaysDark) => line.push_str(" DARK_REMAINING=always"),
+		Some(Darkness::NeverDark) => line.push_str(" DARK_IN=never"),
+		None => (),
+	}
+	if let Some(geocentric) = report.geocentric_latitude {
+		line.push_str(&format!(" GEOCENTRIC_LAT={geocentric:.6}"));
+	}
+	if let Some(sidereal_day) = report.sidereal_day_of_year {
+		line.push_str(&format!(" SIDEREAL_DAY={:.6}", sidereal_day));
+	}
+	line.push_str(&format!(" MJD={}", report.mjd));
+	if let Some(peak) = report.peak {
+		line.push_str(&format!(
+			" PEAK={}",
+			format_countdown(peak.until, options.countdown_style),
+		));
+		if options.reverse_peak {
+			line.push_str(&format!(
+				" SINCE_PEAK={}",
+				format_countdown(peak.since, options.countdown_style)
+			));
+		}
+	}
+	for (label, lmst) in &report.sites {
+		line.push_str(&format!(
+			" LMST_{}={}",
+			compact_key(label),
+			decimal_to_time(*lmst)?.format(TIME_FMT_STRING)
+		));
+	}
+	for (name, until) in &report.events {
+		line.push_str(&format!(
+			" UNTIL_{}={}",
+			compact_key(name),
+			format_countdown(*until, options.countdown_style)
+		));
+	}
+	if let Some((altitude, azimuth)) = report.object_horizontal {
+		line.push_str(&format!(" ALT={:.2} AZ={:.2}", altitude, azimuth));
+	}
+	Ok(line)
+}
+
+/// Replace the degree, prime and double-prime symbols with ASCII and any other non-ASCII
+/// character with `?`.
+fn to_ascii(text: &str) -> String {
+	let mut ascii = String::with_capacity(text.len());
+	for c in text.chars() {
+		match c {
+			'°' => ascii.push_str("deg"),
+			'′' => ascii.push('m'),
+			'″' => ascii.push('s'),
+			c if c.is_ascii() => ascii.push(c),
+			_ => ascii.push('?'),
+		}
+	}
+	ascii
+}
+
+/// Puts the terminal into raw mode for the lifetime of the guard, so single key presses can be
+/// read without waiting for a newline.
+struct RawModeGuard;
+
+impl RawModeGuard {
+	fn enable() -> Result<Self> {
+		crossterm::terminal::enable_raw_mode()?;
+		Ok(Self)
+	}
+}
+
+impl Drop for RawModeGuard {
+	fn drop(&mut self) {
+		let _ = crossterm::terminal::disable_raw_mode();
+	}
+}
+
+enum KeyAction {
+	TogglePause,
+	CopySnapshot,
+	Quit,
+}
+
+/// Wait up to `timeout` for a key press and translate it into an action.
+fn poll_key(timeout: std::time::Duration) -> Result<Option<KeyAction>> {
+	if !event::poll(timeout)? {
+		return Ok(None);
+	}
+	let action = match event::read()? {
+		Event::Key(key) if key.kind == KeyEventKind::Press => match key.code {
+			KeyCode::Char(' ') => Some(KeyAction::TogglePause),
+			KeyCode::Char('c') if key.modifiers.is_empty() => Some(KeyAction::CopySnapshot),
+			KeyCode::Char('q') | KeyCode::Esc => Some(KeyAction::Quit),
+			KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+				Some(KeyAction::Quit)
+			},
+			_ => None,
+		},
+		_ => None,
+	};
+	Ok(action)
+}
+
+/// The countdowns that have come within --notify-within, so each is announced once per approach.
+#[derive(Default)]
+struct Approaches {
+	within: HashSet<String>,
+}
+
+impl Approaches {
+	/// The countdowns of the report that have just come within `within`, with the time left.
+	/// Countdowns restart once their event passes, which counts as leaving.
+	fn arrivals(&mut self, report: &SiderealReport, within: Duration) -> Vec<(String, Duration)> {
+		let countdowns = report
+			.peak
+			.map(|peak| ("Spotiswoode peak".to_owned(), peak.until))
+			.into_iter()
+			.chain(report.events.iter().cloned());
+		let mut arrivals = Vec::new();
+		for (name, until) in countdowns {
+			if until > within {
+				self.within.remove(&name);
+			} else if self.within.insert(name.clone()) {
+				arrivals.push((name, until));
+			}
+		}
+		arrivals
+	}
+}
 
-	let _ = display_info(cli.lat, cli.lon);
+#[cfg(feature = "notify")]
+fn send_notification(message: &str) -> Result<()> {
+	notify_rust::Notification::new()
+		.summary("sidtime")
+		.body(message)
+		.show()?;
 	Ok(())
 }
+
+#[cfg(not(feature = "notify"))]
+fn send_notification(_message: &str) -> Result<()> {
+	Err(anyhow!("built without the notify feature"))
+}
+
+/// The system clipboard, opened on first use and then kept open, since on X11 copied text is
+/// only available while the program that copied it holds the clipboard.
+#[derive(Default)]
+struct Clipboard {
+	#[cfg(feature = "clipboard")]
+	inner: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+	#[cfg(feature = "clipboard")]
+	fn copy(&mut self, text: &str) -> Result<()> {
+		let clipboard = match &mut self.inner {
+			Some(clipboard) => clipboard,
+			None => self.inner.insert(arboard::Clipboard::new()?),
+		};
+		clipboard.set_text(text)?;
+		Ok(())
+	}
+
+	#[cfg(not(feature = "clipboard"))]
+	#[allow(clippy::unused_self)]
+	fn copy(&mut self, _text: &str) -> Result<()> {
+		Err(anyhow!("built without the clipboard feature"))
+	}
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum OutputFormat {
+	/// A block of labelled rows, redrawn in place on a terminal and appended once a second
+	/// otherwise
+	Text,
+	/// One `KEY=value` line per second, suitable for logs
+	HumanCompact,
+	/// One JSON object per second, on a single line unless --json-pretty is given
+	Json,
+}
+
+/// The frame on the terminal, so redrawing an identical one can be skipped.
+#[derive(Default)]
+struct OnScreen(Option<String>);
+
+impl OnScreen {
+	/// Whether `frame` differs from the one on screen, and so needs writing.
+	fn needs(&self, frame: &str) -> bool {
+		self.0.as_deref() != Some(frame)
+	}
+
+	fn show(&mut self, frame: String) {
+		self.0 = Some(frame);
+	}
+
+	/// Forget the frame, once something else has changed the screen.
+	fn clear(&mut self) {
+		self.0 = None;
+	}
+}
+
+/// Appends every computed report to a CSV file.
+struct CsvLogger {
+	writer: BufWriter<File>,
+	sequence: u64,
+	started: Instant,
+}
+
+impl CsvLogger {
+	fn create(path: &Path) -> Result<Self> {
+		let file =
+			File::create(path).with_context(|| format!("Could not create {}", path.display()))?;
+		let mut writer = BufWriter::new(file);
+		writeln!(
+			writer,
+			"sequence,utc,monotonic_seconds,mjd,gmst_hours,lmst_hours"
+		)?;
+		Ok(Self {
+			writer,
+			sequence: 0,
+			started: Instant::now(),
+		})
+	}
+
+	/// Write one row. The sequence number shows dropped rows, and the monotonic clock shows the
+	/// real time between them even if the wall clock jumps.
+	fn log(&mut self, report: &SiderealReport) -> Result<()> {
+		writeln!(
+			self.writer,
+			"{},{},{:.6},{},{:.9},{:.9}",
+			self.sequence,
+			report.utc.format("%FT%T%.6fZ"),
+			self.started.elapsed().as_secs_f64(),
+			report.mjd,
+			report.greenwich_mst,
+			report.local_mst
+		)?;
+		// Flush every row so the log can be followed while the display runs.
+		self.writer.flush()?;
+		self.sequence += 1;
+		Ok(())
+	}
+}
+
+/// One row of a `--log-csv` file.
+struct LoggedRow {
+	utc: DateTime<Utc>,
+	monotonic_seconds: f64,
+	mjd: f64,
+	gmst: f64,
+	lmst: f64,
+}
+
+impl LoggedRow {
+	fn parse(line: &str) -> Result<Self> {
+		let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+		let [_sequence, utc, monotonic_seconds, mjd, gmst, lmst] = fields[..] else {
+			return Err(anyhow!("Expected 6 fields but got {}", fields.len()));
+		};
+		let number = |name: &str, value: &str| -> Result<f64> {
+			value
+				.parse()
+				.map_err(|e| anyhow!("Bad {name} {value:?}: {e}"))
+		};
+		Ok(Self {
+			utc: DateTime::parse_from_rfc3339(utc)
+				.map_err(|e| anyhow!("Bad utc {utc:?}: {e}"))?
+				.with_timezone(&Utc),
+			monotonic_seconds: number("monotonic_seconds", monotonic_seconds)?,
+			mjd: number("mjd", mjd)?,
+			gmst: number("gmst_hours", gmst)?,
+			lmst: number("lmst_hours", lmst)?,
+		})
+	}
+
+	/// The rows of the text display that the log has the values for.
+	fn render(&self) -> Result<String> {
+		Ok(format!(
+			"{:>32}: {}\n{:>32}: {}\n{:>32}: {}\n{:>32}: {}",
+			"Universal Time",
+			self.utc.format("%F %T%.6f UTC"),
+			"Modified Julian Day",
+			self.mjd,
+			"Greenwich mean Sidereal Time",
+			decimal_to_time(self.gmst)?.format(TIME_FMT_STRING),
+			"Local mean Sidereal Time",
+			decimal_to_time(self.lmst)?.format(TIME_FMT_STRING),
+		))
+	}
+}
+
+/// Show each row of a `--log-csv` file in turn, waiting between rows for the logged time between
+/// them divided by `speed`. Rows that can't be read are reported on stderr and skipped.
+fn replay(path: &Path, speed: f64) -> Result<()> {
+	if !(speed.is_finite() && speed > 0.0) {
+		return Err(anyhow!("--speed must be a positive number, got {speed}"));
+	}
+	let file = File::open(path).with_context(|| format!("Could not read {}", path.display()))?;
+	let term = console::Term::stdout();
+	let interactive = is_interactive(&term);
+	let mut last_seconds: Option<f64> = None;
+	let mut lines_to_clear = 0;
+	// The first line is the header.
+	for (index, line) in BufReader::new(file).lines().enumerate().skip(1) {
+		let line = line?;
+		if line.trim().is_empty() {
+			continue;
+		}
+		let parsed = LoggedRow::parse(&line).and_then(|row| {
+			let frame = row.render()?;
+			Ok((row, frame))
+		});
+		let (row, frame) = match parsed {
+			Ok(parsed) => parsed,
+			Err(e) => {
+				eprintln!("{}:{}: skipped: {e}", path.display(), index + 1);
+				continue;
+			},
+		};
+		if let Some(last_seconds) = last_seconds {
+			let wait = ((row.monotonic_seconds - last_seconds) / speed).max(0.0);
+			std::thread::sleep(std::time::Duration::from_secs_f64(wait));
+			if !interactive {
+				// Without cursor movement each frame is appended, so separate them.
+				term.write_line("")?;
+			}
+		}
+		last_seconds = Some(row.monotonic_seconds);
+		if interactive {
+			term.clear_last_lines(lines_to_clear)?;
+			lines_to_clear = screen_rows(&frame, usize::from(term.size().1));
+		}
+		term.write_line(&frame)?;
+	}
+	Ok(())
+}
+
+/// Which midnight ends a day.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum DayBoundary {
+	/// Midnight UTC
+	Utc,
+	/// Midnight in the position's timezone, or UTC when it is unknown
+	Local,
+}
+
+impl DayBoundary {
+	fn date(self, utc: DateTime<Utc>, timezone: Option<Tz>) -> NaiveDate {
+		match (self, timezone) {
+			(Self::Local, Some(timezone)) => utc.with_timezone(&timezone).date_naive(),
+			_ => utc.date_naive(),
+		}
+	}
+}
+
+/// Tells when the clock has moved on to another day, counting from the first instant it sees.
+struct DayWatch {
+	boundary: DayBoundary,
+	start: Option<NaiveDate>,
+}
+
+impl DayWatch {
+	const fn new(boundary: DayBoundary) -> Self {
+		Self {
+			boundary,
+			start: None,
+		}
+	}
+
+	fn crossed(&mut self, now: DateTime<Utc>, timezone: Option<Tz>) -> bool {
+		let date = self.boundary.date(now, timezone);
+		*self.start.get_or_insert(date) != date
+	}
+}
+
+/// Settings that affect how the live display is drawn rather than what is computed.
+struct DisplayOptions {
+	start_paused: bool,
+	ascii_only: bool,
+	format: OutputFormat,
+	/// Indent JSON output instead of keeping each object on one line
+	json_pretty: bool,
+	reverse_peak: bool,
+	/// Stop after this many refreshes, 0 means never
+	max_iterations: u64,
+	/// Altitudes below this are drawn red, as the object is hidden
+	horizon_altitude: f64,
+	/// Altitudes below this are drawn yellow, above it green
+	high_altitude: f64,
+	gmst_at_0h: bool,
+	/// Only redraw when SIGUSR1 arrives instead of on a timer
+	refresh_on_signal: bool,
+	angle_format: AngleFormat,
+	/// Language of the row labels in the text display
+	locale: Locale,
+	/// Show how far each refresh strays from the interval, for diagnosing a loaded system
+	show_drift: bool,
+	/// Print the span the session covered when the display stops
+	summary_on_exit: bool,
+	/// Libastro calls are being timed, so stop cleanly on Ctrl-C to print the statistics
+	profile: bool,
+	/// Lay the text display out in two columns when the terminal is wide enough
+	two_column: bool,
+	/// Terminal width to assume instead of the detected one
+	wrap_width: Option<usize>,
+	/// How to write sidereal times and right ascensions in the text display and transit table
+	time_notation: TimeNotation,
+	align: Align,
+	precision: RowPrecision,
+	/// Skip the timezone lookup and every civil local time row
+	utc_only: bool,
+	/// Use this zone instead of looking one up from the position
+	assume_tz: Option<Tz>,
+	/// Show civil times in this zone, leaving the calculations in the position's
+	output_timezone: Option<Tz>,
+	/// Replace today's date with this one while keeping the time of day, for diagnostics
+	freeze_date: Option<NaiveDate>,
+	/// Exit once the date changes at this boundary
+	until_midnight: Option<DayBoundary>,
+	/// How often to compute a report, overriding the format's default
+	interval: Option<std::time::Duration>,
+	/// Upper limit on frames written to the terminal per second
+	fps: Option<u32>,
+	countdown_style: CountdownStyle,
+	/// Colour the peak countdown on a gradient that turns green within this long
+	peak_imminent: Option<Duration>,
+	/// Highlight countdowns and announce them once they are within this long
+	notify_within: Option<Duration>,
+	/// Also send a desktop notification when a countdown comes within --notify-within
+	notify_desktop: bool,
+	/// Where to log every computed report as CSV
+	log_csv: Option<PathBuf>,
+	/// Show the nautical zone derived from the longitude alone
+	nautical_zone: bool,
+}
+
+/// Whether the terminal can be redrawn in place. Pipes, files, terminals reporting a zero size
+/// and consoles without escape codes get plain appended output instead.
+fn is_interactive(term: &console::Term) -> bool {
+	term.is_term()
+		&& matches!(term.size_checked(), Some((rows, columns)) if rows > 0 && columns > 0)
+		&& enable_ansi()
+}
+
+/// Whether the console understands ANSI escape codes. Legacy Windows consoles only do once
+/// virtual terminal processing is turned on, which this tries to do.
+#[cfg(windows)]
+fn enable_ansi() -> bool {
+	crossterm::ansi_support::supports_ansi()
+}
+
+#[cfg(not(windows))]
+const fn enable_ansi() -> bool {
+	true
+}
+
+const fn refresh_interval(format: OutputFormat, interactive: bool) -> std::time::Duration {
+	match format {
+		OutputFormat::Text if interactive => std::time::Duration::from_micros(200),
+		OutputFormat::Text | OutputFormat::HumanCompact | OutputFormat::Json => {
+			std::time::Duration::from_secs(1)
+		},
+	}
+}
+
+/// The settings a run would use after all defaults are applied, one `key: value` per line.
+fn dump_settings(settings: &ReportSettings, options: &DisplayOptions) -> String {
+	fn or_none<T: std::fmt::Display>(value: Option<T>) -> String {
+		value.map_or_else(|| "none".to_owned(), |value| value.to_string())
+	}
+
+	let mut dump = String::new();
+	let mut line = |key: &str, value: String| dump.push_str(&format!("{key:>17}: {value}\n"));
+
+	let observer = &settings.observer;
+	line("latitude", or_none(observer.latitude));
+	line("longitude", observer.longitude.to_string());
+	line("elevation", format!("{} m", observer.elevation));
+	line("body", format!("{:?}", observer.body));
+	line("algorithm", format!("{:?}", observer.algorithm));
+	line(
+		"refraction",
+		or_none(observer.atmosphere.map(|atmosphere| {
+			format!("{} °C, {} hPa", atmosphere.temperature, atmosphere.pressure)
+		})),
+	);
+	line(
+		"object ra/dec",
+		or_none(settings.object.map(|object| {
+			format!(
+				"{}h {}",
+				object.right_ascension,
+				format_angle(object.declination, options.angle_format)
+			)
+		})),
+	);
+	line("utc only", options.utc_only.to_string());
+	line(
+		"assume tz",
+		or_none(options.assume_tz.map(|timezone| timezone.name())),
+	);
+	line(
+		"output tz",
+		or_none(options.output_timezone.map(|timezone| timezone.name())),
+	);
+	line("freeze date", or_none(options.freeze_date));
+	line(
+		"until midnight",
+		or_none(
+			options
+				.until_midnight
+				.map(|boundary| format!("{boundary:?}")),
+		),
+	);
+	line("nautical zone", options.nautical_zone.to_string());
+	line("format", format!("{:?}", options.format));
+	line("json pretty", options.json_pretty.to_string());
+	line(
+		"refresh interval",
+		format!(
+			"{:?}",
+			options.interval.unwrap_or_else(|| refresh_interval(
+				options.format,
+				is_interactive(&console::Term::stdout())
+			))
+		),
+	);
+	line("max fps", or_none(options.fps));
+	line("max iterations", options.max_iterations.to_string());
+	line(
+		"log csv",
+		or_none(options.log_csv.as_ref().map(|path| path.display())),
+	);
+	line("refresh on signal", options.refresh_on_signal.to_string());
+	line("geocentric lat", settings.geocentric_latitude.to_string());
+	line(
+		"peak time",
+		if settings.spotiswoode_peak {
+			SPOTISWOODE_PEAK_TIME.to_string()
+		} else {
+			"none".to_owned()
+		},
+	);
+	line(
+		"events",
+		or_none((!settings.events.is_empty()).then(|| {
+			settings
+				.events
+				.iter()
+				.map(|event| event.name.as_str())
+				.collect::<Vec<_>>()
+				.join(", ")
+		})),
+	);
+	line(
+		"sites",
+		or_none((!settings.sites.is_empty()).then(|| {
+			settings
+				.sites
+				.iter()
+				.map(|site| format!("{} ({})", site.label, site.longitude))
+				.collect::<Vec<_>>()
+				.join(", ")
+		})),
+	);
+	line("countdown style", format!("{:?}", options.countdown_style));
+	line(
+		"peak imminent",
+		or_none(options.peak_imminent.map(format_duration)),
+	);
+	line(
+		"notify within",
+		or_none(options.notify_within.map(format_duration)),
+	);
+	line("notify desktop", options.notify_desktop.to_string());
+	line("reverse peak", options.reverse_peak.to_string());
+	line("gmst at 0h", options.gmst_at_0h.to_string());
+	line("start paused", options.start_paused.to_string());
+	line("ascii only", options.ascii_only.to_string());
+	line("color", console::colors_enabled().to_string());
+	line("angle format", format!("{:?}", options.angle_format));
+	line("locale", format!("{:?}", options.locale));
+	line("align", format!("{:?}", options.align));
+	line("show drift", options.show_drift.to_string());
+	line("summary on exit", options.summary_on_exit.to_string());
+	line("profile", options.profile.to_string());
+	line("two column", options.two_column.to_string());
+	line("wrap width", or_none(options.wrap_width));
+	line("time notation", format!("{:?}", options.time_notation));
+	line("precision", format!("{:?}", options.precision));
+	line(
+		"horizon altitude",
+		format_angle(options.horizon_altitude, options.angle_format),
+	);
+	line(
+		"high altitude",
+		format_angle(options.high_altitude, options.angle_format),
+	);
+
+	if options.ascii_only {
+		dump = to_ascii(&dump);
+	}
+	dump
+}
+
+/// How often to check for a refresh or interrupt signal while waiting.
+const SIGNAL_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(5);
+
+/// Set a flag whenever the process receives SIGUSR1.
+#[cfg(unix)]
+fn register_refresh_signal() -> Result<Arc<AtomicBool>> {
+	let received = Arc::new(AtomicBool::new(false));
+	signal_hook::flag::register(signal_hook::consts::SIGUSR1, Arc::clone(&received))?;
+	Ok(received)
+}
+
+#[cfg(not(unix))]
+fn register_refresh_signal() -> Result<Arc<AtomicBool>> {
+	Err(anyhow!("Refreshing on a signal is only supported on Unix"))
+}
+
+/// Set a flag when the process is asked to stop with SIGINT or SIGTERM, instead of stopping.
+#[cfg(unix)]
+fn register_interrupt_signal() -> Result<Arc<AtomicBool>> {
+	let received = Arc::new(AtomicBool::new(false));
+	for signal in [signal_hook::consts::SIGINT, signal_hook::consts::SIGTERM] {
+		signal_hook::flag::register(signal, Arc::clone(&received))?;
+	}
+	Ok(received)
+}
+
+/// Other platforms keep the default handling, so an interrupt ends the process straight away.
+#[cfg(not(unix))]
+fn register_interrupt_signal() -> Result<Arc<AtomicBool>> {
+	Ok(Arc::new(AtomicBool::new(false)))
+}
+
+/// The first and last reports computed in a watch session, as UTC and local sidereal time.
+struct Session {
+	start: (DateTime<Utc>, f64),
+	end: (DateTime<Utc>, f64),
+}
+
+impl Session {
+	/// The summary printed by --summary-on-exit.
+	fn summarize(&self, iterations: u64) -> Result<String> {
+		let ((start_utc, start_lmst), (end_utc, end_lmst)) = (self.start, self.end);
+		let sidereal_days = sidereal_days_between(start_utc.naive_utc(), end_utc.naive_utc());
+		Ok([
+			("Iterations", iterations.to_string()),
+			(
+				"Start",
+				format!(
+					"{} (LMST {})",
+					format_zoned_time(&start_utc, 0),
+					decimal_to_time(start_lmst)?.format(TIME_FMT_STRING)
+				),
+			),
+			(
+				"End",
+				format!(
+					"{} (LMST {})",
+					format_zoned_time(&end_utc, 0),
+					decimal_to_time(end_lmst)?.format(TIME_FMT_STRING)
+				),
+			),
+			("Elapsed Civil Time", format_duration(end_utc - start_utc)),
+			(
+				"Elapsed Sidereal Time",
+				format_duration(hours_to_duration(sidereal_days * 24.0)),
+			),
+		]
+		.iter()
+		.map(|(label, value)| format!("{label:>32}: {value}"))
+		.collect::<Vec<_>>()
+		.join("\n"))
+	}
+}
+
+/// Print the output of a one-shot mode, or write it to a file when one is given.
+fn write_output(path: Option<&Path>, text: &str) -> Result<()> {
+	match path {
+		Some(path) => {
+			let file = File::create(path)
+				.with_context(|| format!("Could not create {}", path.display()))?;
+			let mut writer = BufWriter::new(file);
+			writeln!(writer, "{text}")?;
+			writer
+				.flush()
+				.with_context(|| format!("Could not write {}", path.display()))
+		},
+		None => {
+			println!("{text}");
+			Ok(())
+		},
+	}
+}
+
+/// Look up the timezone of the position, or `None` when no lookup is wanted. A zone given with
+/// `--assume-tz` is used without a lookup.
+fn try_resolve_timezone(settings: &ReportSettings, options: &DisplayOptions) -> Option<Result<Tz>> {
+	if let Some(timezone) = options.assume_tz {
+		return Some(Ok(timezone));
+	}
+	let observer = &settings.observer;
+	match observer.latitude {
+		Some(latitude) if !options.utc_only => Some(get_timezone(latitude, observer.longitude)),
+		_ => None,
+	}
+}
+
+fn resolve_timezone(settings: &ReportSettings, options: &DisplayOptions) -> Option<Tz> {
+	try_resolve_timezone(settings, options).and_then(Result::ok)
+}
+
+/// The zone to show civil times in: --output-timezone, or else the position's.
+fn civil_timezone(report: &SiderealReport, options: &DisplayOptions) -> Option<Tz> {
+	options.output_timezone.or(report.timezone)
+}
+
+/// How long to wait before looking up a timezone again after a failed lookup.
+const TIMEZONE_RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// How long to wait before repeating a warning about the timezone being unavailable.
+const TIMEZONE_WARNING_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Lets something happen at most once per interval, starting straight away.
+struct Throttle {
+	interval: std::time::Duration,
+	last: Option<Instant>,
+}
+
+impl Throttle {
+	const fn new(interval: std::time::Duration) -> Self {
+		Self {
+			interval,
+			last: None,
+		}
+	}
+
+	fn ready(&mut self) -> bool {
+		let ready = self.last.is_none_or(|last| last.elapsed() >= self.interval);
+		if ready {
+			self.last = Some(Instant::now());
+		}
+		ready
+	}
+}
+
+/// Fail on the conditions that are otherwise worked around quietly: a timezone that can't be
+/// found for the position, and an object whose altitude can't be computed. `timezone` is the
+/// result of looking up the position's zone, if one was wanted.
+fn check_strict(settings: &ReportSettings, timezone: Option<Result<Tz>>) -> Result<()> {
+	if let Some(Err(e)) = timezone {
+		return Err(e.context("--strict: no single timezone for the position"));
+	}
+	let observer = &settings.observer;
+	if settings.object.is_some() && observer.latitude.is_none() {
+		return Err(anyhow!(
+			"--strict: the object's altitude and azimuth need --lat"
+		));
+	}
+	if settings.object.is_some() && observer.body != Body::Earth {
+		return Err(anyhow!(
+			"--strict: object altitude and azimuth are only computed for the Earth"
+		));
+	}
+	Ok(())
+}
+
+/// Walk through how the sidereal time for an instant is computed, one intermediate value per
+/// step.
+fn explain(utc: DateTime<Utc>, settings: &ReportSettings) -> Result<String> {
+	let observer = settings.observer;
+	let mut steps = Vec::new();
+	let mut step = |value: String, explanation: &str| {
+		steps.push(format!(
+			"{:>2}. {value}\n    {explanation}",
+			steps.len() + 1
+		));
+	};
+
+	step(
+		format!("UTC = {}", utc.format("%FT%T.%6fZ")),
+		"The instant to compute the sidereal time for.",
+	);
+	let ut_hours = utc_to_float(utc.time());
+	step(
+		format!("UT = {ut_hours:.9} h"),
+		"The time of day as decimal hours since 0h UT.",
+	);
+	let gmst = observer.gmst(utc);
+	match (observer.body, observer.algorithm) {
+		(Body::Earth, GmstAlgorithm::Libastro) => {
+			let mjd_at_0h = mjd_from_gregorian_date(utc.date_naive());
+			step(
+				format!("MJD at 0h UT = {mjd_at_0h}"),
+				"Days from libastro's epoch of 1899 December 31 12h UT to the start of the date.",
+			);
+			step(
+				format!("GMST = {gmst:.9} h"),
+				"libastro's utc_gst turns the MJD into the sidereal time at 0h UT, then adds UT \
+				 scaled by the 1.0027379 sidereal hours in each solar hour.",
+			);
+		},
+		(Body::Earth, GmstAlgorithm::Iau1982) => {
+			let centuries = (julian_date(utc.naive_utc()) - J2000) / 36_525.0;
+			step(
+				format!("T = {centuries:.12} centuries"),
+				"Julian centuries since the J2000.0 epoch at 2000 January 1 12h.",
+			);
+			step(
+				format!("GMST = {gmst:.9} h"),
+				"The IAU 1982 cubic in T gives the sidereal time at 0h UT, to which UT scaled by \
+				 1.0027379 sidereal hours per solar hour is added.",
+			);
+		},
+		(Body::Earth, GmstAlgorithm::Iau2006) => {
+			let days = julian_date(utc.naive_utc()) - J2000;
+			step(
+				format!("D = {days:.9} d"),
+				"Days since the J2000.0 epoch at 2000 January 1 12h.",
+			);
+			step(
+				format!("GMST = {gmst:.9} h"),
+				"The Earth rotation angle for D, plus the IAU 2006 polynomial for precession, \
+				 reduced to hours.",
+			);
+		},
+		(Body::Mars, _) => {
+			let days = julian_date(utc.naive_utc()) - J2000;
+			step(
+				format!("D = {days:.9} d"),
+				"Days since the J2000.0 epoch, before converting UTC to TT.",
+			);
+			step(
+				format!("MST at the prime meridian = {gmst:.9} h"),
+				"Mars' prime meridian angle from the IAU rotation model, divided by 15° per hour.",
+			);
+		},
+	}
+	let offset = observer.longitude / 15.0;
+	step(
+		format!("longitude / 15 = {offset:+.9} h"),
+		"Each hour of sidereal time is 15° of longitude, positive to the east.",
+	);
+	let unnormalized = gmst + offset;
+	step(
+		format!("GMST + longitude / 15 = {unnormalized:.9} h"),
+		"The sidereal time at the observer, possibly outside a single day.",
+	);
+	let lmst = normalize_hours(unnormalized);
+	step(
+		format!(
+			"LMST = {lmst:.9} h = {}",
+			decimal_to_time(lmst)?.format(TIME_FMT_STRING)
+		),
+		"Taken modulo 24 hours to land in [0, 24).",
+	);
+	if settings.spotiswoode_peak {
+		let peak = PeakCountdown::new(lmst);
+		step(
+			format!("(13.5 - LMST) mod 24 = {}", format_duration(peak.until)),
+			"The sidereal time left until the Spotiswoode peak at 13:30 LMST.",
+		);
+	}
+
+	Ok(steps.join("\n"))
+}
+
+/// An object to plan around, from `--object-ra` and `--object-dec` or a catalog.
+struct CatalogObject {
+	name: String,
+	position: Equatorial,
+	magnitude: Option<f64>,
+}
+
+/// Read a catalog of `name,ra,dec[,mag]` lines, with right ascension in hours and declination in
+/// degrees, keeping only objects at least as bright as `mag_limit`. A header line starting with
+/// `name`, blank lines and `#` comments are skipped.
+fn load_catalog(path: &Path, mag_limit: Option<f64>) -> Result<Vec<CatalogObject>> {
+	let file = File::open(path).with_context(|| format!("Could not open {}", path.display()))?;
+	let mut objects = Vec::new();
+	// Read line by line so a large catalog is never held in memory as a whole.
+	for (index, line) in BufReader::new(file).lines().enumerate() {
+		let line = line.with_context(|| format!("Could not read {}", path.display()))?;
+		let trimmed = line.trim();
+		if trimmed.is_empty()
+			|| trimmed.starts_with('#')
+			|| (index == 0 && trimmed.to_lowercase().starts_with("name"))
+		{
+			continue;
+		}
+		let error =
+			|message: String| anyhow!("{}:{}: {message} in {trimmed:?}", path.display(), index + 1);
+		let fields = trimmed.split(',').map(str::trim).collect::<Vec<_>>();
+		let (name, right_ascension, declination, magnitude) = match fields.as_slice() {
+			[name, ra, dec] => (name, ra, dec, None),
+			[name, ra, dec, mag] => (name, ra, dec, Some(mag)),
+			_ => return Err(error("Expected name,ra,dec[,mag]".to_owned())),
+		};
+		let number = |what: &str, value: &str| {
+			value
+				.parse::<f64>()
+				.map_err(|e| error(format!("Bad {what} {value:?}: {e}")))
+		};
+		let magnitude = magnitude
+			.filter(|mag| !mag.is_empty())
+			.map(|mag| number("magnitude", mag))
+			.transpose()?;
+		let position = Equatorial {
+			right_ascension: number("right ascension", right_ascension)?,
+			declination: number("declination", declination)?,
+		};
+		// Objects without a magnitude can't be shown to be too faint, so they are kept.
+		if let (Some(limit), Some(magnitude)) = (mag_limit, magnitude) {
+			if magnitude > limit {
+				continue;
+			}
+		}
+		objects.push(CatalogObject {
+			name: (*name).to_owned(),
+			position,
+			magnitude,
+		});
+	}
+	Ok(objects)
+}
+
+/// A table of when each object next crosses the meridian, soonest first, leaving out transits
+/// further away than `window`.
+fn render_transit_table(
+	now: DateTime<Utc>,
+	observer: &Observer,
+	objects: &[CatalogObject],
+	window: Option<Duration>,
+	timezone: Option<Tz>,
+	options: &DisplayOptions,
+) -> Result<String> {
+	if observer.body != Body::Earth {
+		return Err(anyhow!("Transit tables are only available for the Earth"));
+	}
+	// One sidereal time serves every object, which matters for a large catalog.
+	let local_mst = observer.lmst(now);
+	let mut transits = objects
+		.iter()
+		.map(|object| {
+			let sidereal_hours = normalize_hours(object.position.right_ascension - local_mst);
+			(
+				object,
+				now + hours_to_duration(sidereal_hours * SIDEREAL_TO_SOLAR),
+			)
+		})
+		.filter(|(_, transit)| window.is_none_or(|window| *transit - now <= window))
+		.collect::<Vec<_>>();
+	transits.sort_by_key(|(_, transit)| *transit);
+
+	let with_magnitudes = objects.iter().any(|object| object.magnitude.is_some());
+	let mut header = vec!["Object", "RA", "Dec", "Transit", "LMST", "Altitude"];
+	if with_magnitudes {
+		header.push("Mag");
+	}
+	let mut rows = vec![header.into_iter().map(str::to_owned).collect::<Vec<_>>()];
+	for (object, transit) in transits {
+		let civil = match timezone {
+			Some(timezone) => transit
+				.with_timezone(&timezone)
+				.format("%F %T %Z")
+				.to_string(),
+			None => transit.format("%F %T UTC").to_string(),
+		};
+		let altitude = match observer.transit_altitude(object.position) {
+			Some(altitude) if altitude < 0.0 => "below horizon".to_owned(),
+			Some(altitude) => format_angle(altitude, options.angle_format),
+			None => "needs --lat".to_owned(),
+		};
+		let mut row = vec![
+			object.name.clone(),
+			format_sidereal_time(
+				normalize_hours(object.position.right_ascension),
+				0,
+				options.time_notation,
+			)?,
+			format_angle(object.position.declination, options.angle_format),
+			civil,
+			format_sidereal_time(observer.lmst(transit), 0, options.time_notation)?,
+			altitude,
+		];
+		if with_magnitudes {
+			row.push(
+				object
+					.magnitude
+					.map_or_else(String::new, |magnitude| format!("{magnitude:.1}")),
+			);
+		}
+		rows.push(row);
+	}
+
+	let mut widths = vec![0; rows[0].len()];
+	for row in &rows {
+		for (width, cell) in widths.iter_mut().zip(row) {
+			*width = (*width).max(cell.chars().count());
+		}
+	}
+	let table = rows
+		.iter()
+		.map(|row| {
+			let cells = row
+				.iter()
+				.zip(&widths)
+				.map(|(cell, &width)| format!("{cell:<width$}"))
+				.collect::<Vec<_>>();
+			cells.join("  ").trim_end().to_owned()
+		})
+		.collect::<Vec<_>>()
+		.join("\n");
+	Ok(if options.ascii_only {
+		to_ascii(&table)
+	} else {
+		table
+	})
+}
+
+/// Fill in the `{lmst}`, `{gmst}`, `{mjd}` and `{local}` placeholders of a status template. As in
+/// Rust's format strings, `{{` and `}}` are literal braces and a lone `}` is an error. `{local}` is
+/// in `timezone`, or UTC without one.
+fn render_template(
+	template: &str,
+	report: &SiderealReport,
+	timezone: Option<Tz>,
+) -> Result<String> {
+	let mut output = String::new();
+	let mut rest = template;
+	while let Some(brace) = rest.find(['{', '}']) {
+		output.push_str(&rest[..brace]);
+		let brace_char = char::from(rest.as_bytes()[brace]);
+		rest = &rest[brace + 1..];
+		if let Some(after) = rest.strip_prefix(brace_char) {
+			output.push(brace_char);
+			rest = after;
+			continue;
+		}
+		if brace_char == '}' {
+			return Err(anyhow!(
+				"Unmatched }} in template {template:?}, write }}}} for a literal brace"
+			));
+		}
+		let close = rest
+			.find('}')
+			.ok_or_else(|| anyhow!("Unclosed {{ in template {template:?}"))?;
+		let value = match &rest[..close] {
+			"lmst" => decimal_to_time(report.local_mst)?.format("%T").to_string(),
+			"gmst" => decimal_to_time(report.greenwich_mst)?
+				.format("%T")
+				.to_string(),
+			"mjd" => format!("{:.5}", report.mjd),
+			"local" => match timezone {
+				Some(timezone) => report.utc.with_timezone(&timezone).format("%T").to_string(),
+				None => report.utc.format("%T").to_string(),
+			},
+			name => return Err(anyhow!("Unknown placeholder {{{name}}} in template")),
+		};
+		output.push_str(&value);
+		rest = &rest[close + 1..];
+	}
+	output.push_str(rest);
+	Ok(output)
+}
+
+/// The report in the selected output format.
+fn render(report: &SiderealReport, options: &DisplayOptions) -> Result<String> {
+	match options.format {
+		OutputFormat::Text => render_info(report, options),
+		OutputFormat::HumanCompact => render_compact(report, options),
+		OutputFormat::Json => render_json(report, options),
+	}
+}
+
+/// The report as a JSON object. Times of day are decimal hours and countdowns are seconds.
+#[allow(clippy::cast_precision_loss)]
+fn render_json(report: &SiderealReport, options: &DisplayOptions) -> Result<String> {
+	let seconds = |duration: Duration| duration.num_milliseconds() as f64 / 1000.0;
+	let darkness = report.darkness.map(|darkness| match darkness {
+		Darkness::Dark(remaining) => json!({ "dark": true, "seconds": seconds(remaining) }),
+		Darkness::Light(until) => json!({ "dark": false, "seconds": seconds(until) }),
+		Darkness::AlwaysDark => json!({ "dark": true, "seconds": null }),
+		Darkness::NeverDark => json!({ "dark": false, "seconds": null }),
+	});
+	let value = json!({
+		"utc": report.utc.to_rfc3339(),
+		"latitude": report.latitude,
+		"longitude": report.longitude,
+		"timezone": report.timezone.map(|timezone| timezone.name()),
+		"body": match report.body {
+			Body::Earth => "earth",
+			Body::Mars => "mars",
+		},
+		"mjd": report.mjd,
+		"gmst": report.greenwich_mst,
+		"lmst": report.local_mst,
+		"gmst_at_0h": report.gmst_at_0h.filter(|_| options.gmst_at_0h),
+		"geocentric_latitude": report.geocentric_latitude,
+		"zenith": report.zenith.map(|zenith| json!({
+			"right_ascension": zenith.right_ascension,
+			"declination": zenith.declination,
+		})),
+		"zenith_constellation": report.zenith_constellation.as_ref().map(|constellation| json!({
+			"abbreviation": constellation.abbreviation,
+			"name": constellation.name,
+		})),
+		"sidereal_day_of_year": report.sidereal_day_of_year,
+		"apparent_solar_time": report.apparent_solar_time,
+		"solar_hour_angle": report.solar_hour_angle,
+		"lmst_at_solar_noon": report.lmst_at_solar_noon,
+		"sidereal_midnight": report.sidereal_midnight.map(|midnight| midnight.to_rfc3339()),
+		"peak": report.peak.map(|peak| json!({
+			"until": seconds(peak.until),
+			"since": seconds(peak.since),
+		})),
+		"events": report
+			.events
+			.iter()
+			.map(|(name, until)| json!({ "name": name, "until": seconds(*until) }))
+			.collect::<Vec<_>>(),
+		"sites": report
+			.sites
+			.iter()
+			.map(|(label, lmst)| json!({ "label": label, "lmst": lmst }))
+			.collect::<Vec<_>>(),
+		"darkness": darkness,
+		"object": report.object_horizontal.map(|(altitude, azimuth)| json!({
+			"altitude": altitude,
+			"azimuth": azimuth,
+		})),
+	});
+	Ok(if options.json_pretty {
+		serde_json::to_string_pretty(&value)?
+	} else {
+		serde_json::to_string(&value)?
+	})
+}
+
+/// Render the report for a single instant in the chosen format.
+fn render_once(
+	utc: DateTime<Utc>,
+	settings: &ReportSettings,
+	options: &DisplayOptions,
+) -> Result<String> {
+	let report = SiderealReport::new(utc, settings, resolve_timezone(settings, options))?;
+	let output = render(&report, options)?;
+	Ok(if options.ascii_only {
+		to_ascii(&output)
+	} else {
+		output
+	})
+}
+
+/// Parse an RFC 3339 timestamp, or a date and time without an offset which is taken as UTC.
+fn parse_datetime(value: &str) -> Result<DateTime<Utc>> {
+	if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+		return Ok(datetime.with_timezone(&Utc));
+	}
+	[
+		"%Y-%m-%d %H:%M:%S%.f",
+		"%Y-%m-%dT%H:%M:%S%.f",
+		"%Y-%m-%d %H:%M",
+	]
+	.iter()
+	.find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+	.map(|naive| naive.and_utc())
+	.ok_or_else(|| anyhow!("Expected RFC 3339 or YYYY-MM-DD HH:MM:SS"))
+}
+
+/// Parse seconds since the Unix epoch, with an optional fractional part.
+#[allow(clippy::cast_possible_truncation)]
+#[allow(clippy::cast_sign_loss)]
+fn parse_unix_timestamp(value: &str) -> std::result::Result<DateTime<Utc>, String> {
+	let seconds: f64 = value
+		.parse()
+		.map_err(|e| format!("Not a number of seconds: {e}"))?;
+	let out_of_range = || format!("Timestamp {value} is outside the supported range");
+	// Beyond this an f64 can't represent whole seconds exactly, and chrono's range ends long
+	// before it anyway.
+	if !seconds.is_finite() || seconds.abs() > 2f64.powi(53) {
+		return Err(out_of_range());
+	}
+	let whole = seconds.floor();
+	let nanos = ((seconds - whole) * 1_000_000_000.0).round() as u32;
+	let (whole, nanos) = if nanos >= 1_000_000_000 {
+		(whole as i64 + 1, nanos - 1_000_000_000)
+	} else {
+		(whole as i64, nanos)
+	};
+	DateTime::from_timestamp(whole, nanos).ok_or_else(out_of_range)
+}
+
+/// The lines laid out in two columns, the first half on the left, or `None` when they don't fit
+/// side by side in `width` columns.
+fn two_columns(text: &str, width: usize) -> Option<String> {
+	const GAP: usize = 2;
+	let lines = text.lines().collect::<Vec<_>>();
+	let (left, right) = lines.split_at(lines.len().div_ceil(2));
+	let widest = |lines: &[&str]| {
+		lines
+			.iter()
+			.map(|line| console::measure_text_width(line))
+			.max()
+			.unwrap_or(0)
+	};
+	let left_width = widest(left);
+	if left_width + GAP + widest(right) > width {
+		return None;
+	}
+	let rows = left
+		.iter()
+		.enumerate()
+		.map(|(index, line)| match right.get(index) {
+			Some(other) => {
+				let padding = left_width - console::measure_text_width(line) + GAP;
+				format!("{line}{}{other}", " ".repeat(padding))
+			},
+			None => (*line).to_owned(),
+		})
+		.collect::<Vec<_>>();
+	Some(rows.join("\n"))
+}
+
+/// How many terminal rows the text takes when lines wrap at `width` columns.
+fn screen_rows(text: &str, width: usize) -> usize {
+	text.split('\n')
+		.map(|line| {
+			let columns = console::measure_text_width(line);
+			if width == 0 || columns == 0 {
+				1
+			} else {
+				columns.div_ceil(width)
+			}
+		})
+		.sum()
+}
+
+fn display_info(
+	settings: &ReportSettings,
+	options: &DisplayOptions,
+	clock: &dyn Clock,
+) -> Result<()> {
+	let term = console::Term::buffered_stdout();
+	let interactive = is_interactive(&term);
+	let refresh_interval = options
+		.interval
+		.unwrap_or_else(|| refresh_interval(options.format, interactive));
+	let min_frame_time = options
+		.fps
+		.map(|fps| std::time::Duration::from_secs_f64(1.0 / f64::from(fps)));
+	let mut last_frame: Option<Instant> = None;
+	let mut on_screen = OnScreen::default();
+
+	// A failed lookup is retried now and then instead of on every refresh, and only warned about
+	// now and then, so a transient failure doesn't flood the terminal.
+	let mut timezone = None;
+	let mut timezone_retry = Throttle::new(TIMEZONE_RETRY_INTERVAL);
+	let mut timezone_warning = Throttle::new(TIMEZONE_WARNING_INTERVAL);
+
+	// Raw mode is needed to read the spacebar without a newline. It also disables the terminal's
+	// own handling of Ctrl-C, so that key is handled below and the guard restores the terminal.
+	let raw_mode = if interactive {
+		RawModeGuard::enable().ok()
+	} else {
+		None
+	};
+	let line_ending = if raw_mode.is_some() { "\r\n" } else { "\n" };
+
+	let refresh_signal = if options.refresh_on_signal {
+		Some(register_refresh_signal()?)
+	} else {
+		None
+	};
+
+	// Without raw mode Ctrl-C would end the process before the summary or the profile could be
+	// printed.
+	let interrupted = if (options.summary_on_exit || options.profile) && raw_mode.is_none() {
+		register_interrupt_signal()?
+	} else {
+		Arc::new(AtomicBool::new(false))
+	};
+	let mut session: Option<Session> = None;
+
+	let mut csv_logger = options
+		.log_csv
+		.as_deref()
+		.map(CsvLogger::create)
+		.transpose()?;
+
+	let mut day_watch = options.until_midnight.map(DayWatch::new);
+	let mut paused = options.start_paused;
+	let mut frozen: Option<SiderealReport> = None;
+	let mut lines_to_clear = 0;
+	let mut iterations: u64 = 0;
+	let mut last_tick: Option<Instant> = None;
+	let mut clipboard = Clipboard::default();
+	let mut approaches = Approaches::default();
+	// Feedback on a key press, shown with the next frame.
+	let mut notice: Option<String> = None;
+	let mut terminal_size = term.size();
+	// A warning waiting to be shown. On an interactive screen it is drawn as part of the next
+	// frame, so it is counted in the rows cleared before the frame after that.
+	let mut warning = None;
+	let warnings_in_frame = interactive && matches!(options.format, OutputFormat::Text);
+
+	loop {
+		if timezone.is_none() && timezone_retry.ready() {
+			match try_resolve_timezone(settings, options) {
+				Some(Ok(resolved)) => timezone = Some(resolved),
+				Some(Err(e)) if timezone_warning.ready() => {
+					warning = Some(format!("Timezone unavailable: {e}"));
+				},
+				_ => (),
+			}
+		}
+
+		// How much longer the last wait took than asked for. Waiting on a signal has no expected
+		// length, so there is nothing to measure.
+		let tick = Instant::now();
+		let drift = last_tick
+			.filter(|_| !options.refresh_on_signal)
+			.map(|last| tick.duration_since(last).as_secs_f64() - refresh_interval.as_secs_f64());
+		last_tick = Some(tick);
+
+		let now = clock.now();
+		if day_watch
+			.as_mut()
+			.is_some_and(|watch| watch.crossed(now, timezone))
+		{
+			break;
+		}
+
+		if !paused {
+			frozen = None;
+		}
+		let report = match frozen.take() {
+			Some(report) => report,
+			None => {
+				let report = SiderealReport::new(now, settings, timezone)?;
+				if let Some(logger) = &mut csv_logger {
+					logger.log(&report)?;
+				}
+				let point = (report.utc, report.local_mst);
+				session
+					.get_or_insert(Session {
+						start: point,
+						end: point,
+					})
+					.end = point;
+				if let Some(within) = options.notify_within {
+					let arrivals = approaches
+						.arrivals(&report, within)
+						.into_iter()
+						.map(|(name, until)| format!("{name} in {}", format_duration(until)))
+						.collect::<Vec<_>>();
+					if !arrivals.is_empty() {
+						let message = arrivals.join(", ");
+						notice = Some(
+							match options.notify_desktop.then(|| send_notification(&message)) {
+								Some(Err(e)) => format!("{message} (no desktop notification: {e})"),
+								_ => message,
+							},
+						);
+					}
+				}
+				report
+			},
+		};
+
+		// Reports are computed every interval, but the terminal only gets a new frame when the
+		// frame rate allows it.
+		let frame_due = match (min_frame_time, last_frame) {
+			(Some(min_frame_time), Some(last_frame)) => last_frame.elapsed() >= min_frame_time,
+			_ => true,
+		};
+
+		match options.format {
+			_ if !frame_due => (),
+			OutputFormat::Text => {
+				// After a resize the terminal may have rewrapped the last frame, so the count of
+				// rows to clear no longer holds. Start again from a clear screen instead.
+				if interactive && term.size() != terminal_size {
+					terminal_size = term.size();
+					term.clear_screen()?;
+					lines_to_clear = 0;
+					on_screen.clear();
+				}
+				let width = options
+					.wrap_width
+					.unwrap_or_else(|| usize::from(terminal_size.1));
+
+				let mut info = render_info(&report, options)?;
+				if options.two_column {
+					// Fall back to one column when two don't fit.
+					if let Some(columns) = two_columns(&info, width) {
+						info = columns;
+					}
+				}
+				if let (true, Some(drift)) = (options.show_drift, drift) {
+					info.push_str(&format!(
+						"\n{:>32}: {:+.3} ms",
+						options.locale.label("Loop Drift"),
+						drift * 1000.0
+					));
+				}
+				if paused {
+					info.push_str(
+						"\n                                  [PAUSED - press space to resume]",
+					);
+				}
+				if let Some(notice) = &notice {
+					info.push_str(&format!("\n                                  [{notice}]"));
+				}
+				if let (true, Some(warning)) = (warnings_in_frame, &warning) {
+					info.push_str(&format!("\n                                  [{warning}]"));
+				}
+				if options.ascii_only {
+					info = to_ascii(&info);
+				}
+
+				// Redrawing a frame identical to the one on screen would only cause flicker.
+				if on_screen.needs(&info) {
+					if interactive {
+						term.clear_last_lines(lines_to_clear)?;
+						// Lines wider than the terminal wrap onto more rows, which all need
+						// clearing.
+						lines_to_clear = screen_rows(&info, width);
+					} else if last_frame.is_some() {
+						// Without cursor movement each frame is appended, so separate them.
+						term.write_str(line_ending)?;
+					}
+					term.write_str(&info.replace('\n', line_ending))?;
+					term.write_str(line_ending)?;
+					term.flush()?;
+					last_frame = Some(Instant::now());
+					on_screen.show(info);
+					notice = None;
+					if warnings_in_frame {
+						warning = None;
+					}
+				}
+			},
+			// A paused log simply stops growing.
+			OutputFormat::HumanCompact | OutputFormat::Json if !paused => {
+				let mut line = render(&report, options)?;
+				if options.ascii_only {
+					line = to_ascii(&line);
+				}
+				term.write_str(&line)?;
+				term.write_str(line_ending)?;
+				term.flush()?;
+				last_frame = Some(Instant::now());
+			},
+			OutputFormat::HumanCompact | OutputFormat::Json => (),
+		}
+
+		// Nothing on the screen is cleared otherwise, so the warning can go straight to stderr.
+		if let Some(warning) = warning.take_if(|_| !warnings_in_frame) {
+			eprint!("{warning}{line_ending}");
+		}
+		// Compact lines and JSON are meant for logs, so feedback goes to stderr instead.
+		if let (OutputFormat::HumanCompact | OutputFormat::Json, Some(notice)) =
+			(options.format, notice.take())
+		{
+			eprint!("{notice}{line_ending}");
+		}
+
+		let report = if paused {
+			&*frozen.insert(report)
+		} else {
+			&report
+		};
+
+		iterations += 1;
+		if options.max_iterations != 0 && iterations >= options.max_iterations {
+			break;
+		}
+
+		let wait = |timeout: std::time::Duration| -> Result<Option<KeyAction>> {
+			if raw_mode.is_some() {
+				poll_key(timeout)
+			} else {
+				// Sleep in slices so an interrupt ends the wait instead of the whole interval.
+				let deadline = Instant::now() + timeout;
+				while !interrupted.load(Ordering::Relaxed) {
+					let left = deadline.saturating_duration_since(Instant::now());
+					if left.is_zero() {
+						break;
+					}
+					std::thread::sleep(left.min(SIGNAL_POLL_INTERVAL));
+				}
+				Ok(None)
+			}
+		};
+		let action = match &refresh_signal {
+			// Keep handling keys while waiting, and redraw straight away when one is pressed.
+			Some(signal) => loop {
+				let action = wait(SIGNAL_POLL_INTERVAL)?;
+				if action.is_some()
+					|| signal.swap(false, Ordering::Relaxed)
+					|| interrupted.load(Ordering::Relaxed)
+				{
+					break action;
+				}
+			},
+			None => wait(refresh_interval)?,
+		};
+		match action {
+			Some(KeyAction::TogglePause) => paused = !paused,
+			Some(KeyAction::CopySnapshot) => {
+				// Pasted text has no use for colour codes, and --ascii-only applies to it as well
+				// as to the screen.
+				let snapshot = console::strip_ansi_codes(&render(report, options)?).into_owned();
+				let snapshot = if options.ascii_only {
+					to_ascii(&snapshot)
+				} else {
+					snapshot
+				};
+				notice = Some(match clipboard.copy(&snapshot) {
+					Ok(()) => "Copied to clipboard".to_owned(),
+					Err(e) => format!("Could not copy to clipboard: {e}"),
+				});
+			},
+			Some(KeyAction::Quit) => break,
+			None => (),
+		}
+		if interrupted.load(Ordering::Relaxed) {
+			break;
+		}
+	}
+
+	if let (true, Some(session)) = (options.summary_on_exit, session) {
+		term.flush()?;
+		// Leave raw mode first so the summary's lines start at the left edge.
+		drop(raw_mode);
+		println!("{}", session.summarize(iterations)?);
+	}
+	Ok(())
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "sidtime")]
+// Every way of supplying a position joins this group, so at most one of them is used. Without
+// any of them the saved home position is used. --lat only goes with --lon.
+#[command(group(ArgGroup::new("coordinates").args(["lon", "midpoint_of"])))]
+// The modes that print once and exit, which --output applies to.
+#[command(group(
+	ArgGroup::new("one_shot")
+		.args(["unix", "sample_at", "status", "explain"])
+		.multiple(true)
+))]
+/// Prints shows the local sidereal time given a longitude.
+struct Cli {
+	#[command(subcommand)]
+	command: Option<Command>,
+	/// Latitude in decimal degrees or as `D° M′ S″` (+ for N - for S, or a trailing N or S)
+	#[arg(long, value_parser = parse_latitude, allow_hyphen_values = true)]
+	lat: Option<f64>,
+	/// Longitude in decimal degrees or as `D° M′ S″` (+ for E - for W, or a trailing E or W)
+	#[arg(long, value_parser = parse_longitude, allow_hyphen_values = true)]
+	lon: Option<f64>,
+	/// Observe from the great-circle midpoint of two positions given as LAT,LON
+	#[arg(
+		long,
+		num_args = 2,
+		value_names = ["LAT1,LON1", "LAT2,LON2"],
+		value_parser = parse_coordinates,
+		allow_hyphen_values = true,
+		action = ArgAction::Set,
+		conflicts_with = "lat"
+	)]
+	midpoint_of: Vec<(f64, f64)>,
+	/// Read --lon as positive to the west, the convention of some astronomy references
+	#[arg(long, requires = "lon")]
+	lon_west_positive: bool,
+	/// Read --lat and --lon as radians rather than degrees
+	#[arg(long, requires = "lon")]
+	radians: bool,
+	/// Also show the geocentric latitude, measured at the Earth's centre, of the WGS84 geodetic
+	/// --lat
+	#[arg(long, requires = "lat")]
+	geocentric_lat: bool,
+	/// Elevation above the surrounding horizon in metres, which brings sunrise earlier
+	#[arg(long, default_value_t = 0.0)]
+	elevation: f64,
+	/// Show apparent altitudes, raised by atmospheric refraction, instead of geometric ones
+	#[arg(long)]
+	refraction: bool,
+	/// Air temperature in °C for --refraction
+	#[arg(long, default_value_t = Atmosphere::default().temperature, requires = "refraction", allow_hyphen_values = true)]
+	temperature: f64,
+	/// Air pressure in hPa for --refraction
+	#[arg(long, default_value_t = Atmosphere::default().pressure, requires = "refraction")]
+	pressure: f64,
+	/// Start with the display frozen; press space to pause/resume, c to copy the report to the
+	/// clipboard and q to quit
+	#[arg(long)]
+	start_paused: bool,
+	/// Only print ASCII, e.g. `deg` instead of `°`
+	#[arg(long)]
+	ascii_only: bool,
+	/// How to print the report
+	#[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+	format: OutputFormat,
+	/// Indent --format json output for reading instead of keeping each object on one line
+	#[arg(long)]
+	json_pretty: bool,
+	/// How to compute Greenwich mean sidereal time
+	#[arg(long, visible_alias = "model", value_enum, default_value_t = GmstAlgorithm::Libastro)]
+	algorithm: GmstAlgorithm,
+	/// Leave out the countdown to the Spotiswoode peak
+	#[arg(long, conflicts_with = "reverse_peak")]
+	no_peak: bool,
+	/// Count down to each `name,HH:MM:SS` local sidereal time listed in this file, one per line
+	#[arg(long, value_name = "FILE")]
+	events_file: Option<PathBuf>,
+	/// Also show the local sidereal time at each `longitude[,label]` listed in this file, one per
+	/// line, with longitudes in degrees east
+	#[arg(long, value_name = "FILE")]
+	longitudes_file: Option<PathBuf>,
+	/// List the sites from --longitudes-file from west to east instead of in file order
+	#[arg(long, requires = "longitudes_file")]
+	sort_longitudes: bool,
+	/// Also show the sidereal time elapsed since the last Spotiswoode peak
+	#[arg(long)]
+	reverse_peak: bool,
+	/// Time between computations, in milliseconds or with a unit like `500ms`, `2s` or `1m`
+	/// [default: 1s for logs and pipes, 0.2ms on a terminal]
+	#[arg(long, visible_alias = "every", value_name = "DURATION", value_parser = parse_interval)]
+	interval: Option<std::time::Duration>,
+	/// Redraw the terminal at most this many times per second, however often values are computed
+	#[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+	fps: Option<u32>,
+	/// Log every computed report to this CSV file, replacing it if it exists
+	#[arg(long, value_name = "FILE")]
+	log_csv: Option<PathBuf>,
+	/// Compute a single report for this many seconds since the Unix epoch and exit
+	#[arg(long, value_name = "SECONDS", value_parser = parse_unix_timestamp, allow_hyphen_values = true)]
+	unix: Option<DateTime<Utc>>,
+	/// Compute a report for this instant and exit; repeat for several, which are shown in order.
+	/// Takes RFC 3339, or `YYYY-MM-DD HH:MM:SS` in UTC
+	#[arg(long, value_name = "DATETIME", conflicts_with = "unix")]
+	sample_at: Vec<String>,
+	/// Print one short line from --template for the current time and exit, for status bars
+	#[arg(long, conflicts_with_all = ["unix", "sample_at"])]
+	status: bool,
+	/// Template for --status, with `{lmst}`, `{gmst}`, `{mjd}` and `{local}` placeholders, and
+	/// `{{` and `}}` for literal braces
+	#[arg(long, requires = "status", default_value = "LST {lmst}")]
+	template: String,
+	/// Write the output of --unix, --sample-at, --status or --explain to this file instead of
+	/// stdout, without color codes
+	#[arg(long, value_name = "FILE", requires = "one_shot")]
+	output: Option<PathBuf>,
+	/// Print each step of computing the sidereal time, for the --unix time or now, and exit
+	#[arg(long, conflicts_with_all = ["sample_at", "status"])]
+	explain: bool,
+	/// Exit after this many refreshes instead of running forever (0 runs forever)
+	#[arg(long, value_name = "N", default_value_t = 0)]
+	max_iterations: u64,
+	/// Right ascension in hours of an object to track, needs --lat for altitude and azimuth.
+	/// Repeat with --object-dec for several objects to print a table of their next transits
+	#[arg(long, requires = "object_dec")]
+	object_ra: Vec<f64>,
+	/// Declination in degrees of the object to track, one for each --object-ra
+	#[arg(long, requires = "object_ra")]
+	object_dec: Vec<f64>,
+	/// Print the objects in this `name,ra,dec[,mag]` file that transit within --window, soonest
+	/// first, and exit. Right ascension is in hours and declination in degrees
+	#[arg(long, value_name = "FILE", conflicts_with = "object_ra")]
+	catalog: Option<PathBuf>,
+	/// Leave out catalog objects fainter than this magnitude
+	#[arg(
+		long,
+		value_name = "MAG",
+		requires = "catalog",
+		allow_hyphen_values = true
+	)]
+	mag_limit: Option<f64>,
+	/// How many hours ahead to list catalog transits
+	#[arg(
+		long,
+		value_name = "HOURS",
+		requires = "catalog",
+		default_value_t = 12.0
+	)]
+	window: f64,
+	/// Altitude in degrees below which the object counts as hidden
+	#[arg(long, default_value_t = 0.0)]
+	horizon_altitude: f64,
+	/// Altitude in degrees above which the object counts as well placed
+	#[arg(long, default_value_t = 20.0)]
+	high_altitude: f64,
+	/// Disable colored output
+	#[arg(long)]
+	no_color: bool,
+	/// Also show GMST at 0h UT of the current date, as tabulated in almanacs
+	#[arg(long = "gmst-at-0h")]
+	gmst_at_0h: bool,
+	/// Only redraw when the process receives SIGUSR1, e.g. from a pulse-per-second source (Unix
+	/// only)
+	#[cfg(unix)]
+	#[arg(long)]
+	refresh_on_signal: bool,
+	/// Also show the nautical timezone, one hour per 15° of longitude, as used at sea
+	#[arg(long = "round-longitude-zone")]
+	nautical_zone: bool,
+	/// Only show UTC based rows, skipping the timezone lookup even when --lat is given
+	#[arg(long)]
+	utc_only: bool,
+	/// Use this IANA timezone for the civil time instead of looking up the one at the position
+	#[arg(long, value_name = "NAME", value_parser = parse_timezone, conflicts_with = "utc_only")]
+	assume_tz: Option<Tz>,
+	/// Show civil times in this IANA timezone instead of the position's, e.g. your own when
+	/// planning for a distant site. Unlike --assume-tz it leaves the calculations alone
+	#[arg(long, value_name = "NAME", value_parser = parse_timezone, conflicts_with = "utc_only")]
+	output_timezone: Option<Tz>,
+	/// Diagnostic: hold the date fixed while the time of day follows the clock, to watch a whole
+	/// simulated day
+	#[arg(long, hide = true, value_name = "YYYY-MM-DD")]
+	freeze_date: Option<NaiveDate>,
+	/// Diagnostic: print the raw arguments and results of the libastro calls for the current
+	/// instant to stderr before carrying on as usual
+	#[arg(long, hide = true)]
+	debug_ffi: bool,
+	/// Diagnostic: print how long the timezone finder's construction, argument parsing and the
+	/// first report take, then exit
+	#[arg(long, hide = true)]
+	benchmark_startup: bool,
+	/// Exit cleanly when the date rolls over, at UTC midnight or the position's local midnight
+	#[arg(long, value_enum, value_name = "BOUNDARY", num_args = 0..=1, default_missing_value = "utc")]
+	refresh_until_midnight: Option<DayBoundary>,
+	/// How to show countdowns to daily events such as the Spotiswoode peak
+	#[arg(long, value_enum, default_value_t = CountdownStyle::Wrap)]
+	countdown_style: CountdownStyle,
+	/// Colour the countdown to the Spotiswoode peak from red when it's far off to green when
+	/// it's imminent
+	#[arg(long, conflicts_with = "no_peak")]
+	color_scale_peak: bool,
+	/// Minutes before the peak from which --color-scale-peak shows it as imminent
+	#[arg(
+		long,
+		value_name = "MINUTES",
+		default_value_t = 30,
+		requires = "color_scale_peak"
+	)]
+	peak_imminent: u32,
+	/// Highlight the peak and --events-file countdowns once they are this close, e.g. `10m`, and
+	/// announce each one once per approach
+	#[arg(long, value_name = "DURATION", value_parser = parse_interval)]
+	notify_within: Option<std::time::Duration>,
+	/// Also announce countdowns coming within --notify-within with a desktop notification. Needs
+	/// the `notify` feature
+	#[arg(long, requires = "notify_within")]
+	notify_desktop: bool,
+	/// How to write angles such as altitude, azimuth and declination in the text display
+	#[arg(long, value_enum, default_value_t = AngleFormat::Decimal)]
+	angle_format: AngleFormat,
+	/// Language of the text display's labels; defaults to the one in LC_ALL, LC_MESSAGES or LANG
+	#[arg(long, value_enum)]
+	locale: Option<Locale>,
+	/// How to write sidereal times and right ascensions in the text display and transit table
+	#[arg(long, value_enum, default_value_t = TimeNotation::Colon)]
+	time_notation: TimeNotation,
+	/// Show the text display in two columns side by side when the terminal is wide enough for
+	/// them, to save rows
+	#[arg(long)]
+	two_column: bool,
+	/// Assume the terminal is this many columns wide when working out how many rows a wrapped
+	/// frame takes and whether --two-column fits, instead of asking the terminal
+	#[arg(long, value_name = "COLS", value_parser = clap::value_parser!(usize))]
+	wrap_width: Option<usize>,
+	/// When the display stops, print how many refreshes there were and the civil and sidereal time
+	/// between the first and last
+	#[arg(long)]
+	summary_on_exit: bool,
+	/// Time the calls to libastro's cal_mjd and utc_gst, and print how many there were and how
+	/// long they took on exit
+	#[arg(long)]
+	profile: bool,
+	/// Show how much longer than the refresh interval each refresh took
+	#[arg(long)]
+	show_drift: bool,
+	/// How to line up the values of the text display
+	#[arg(long, value_enum, default_value_t = Align::Left)]
+	align: Align,
+	/// Digits after the decimal point per row of the text display, e.g. `lmst=0,mjd=6`. Rows are
+	/// utc, local, mjd, gmst, lmst, gmst0h, solar and coords for the position in the first row;
+	/// the times default to 6 digits and the position to 1
+	#[arg(long, value_name = "ROW=DIGITS,...", value_parser = parse_row_precision)]
+	precision: Option<RowPrecision>,
+	/// Exit with an error instead of quietly leaving rows out: when no single timezone covers the
+	/// position, or when an object's altitude can't be computed for lack of --lat or on Mars
+	#[arg(long)]
+	strict: bool,
+	/// Print the settings that would be used and exit
+	#[arg(long)]
+	dump_args: bool,
+	/// Which body's sidereal time to show. Mars uses a constant rotation rate and hides the rows
+	/// that only make sense on Earth
+	#[arg(long, value_enum, default_value_t = Body::Earth)]
+	body: Body,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+	/// Save a home position to use when no coordinates are given
+	SetHome {
+		/// Latitude (+ for N - for S, or a trailing N or S)
+		#[arg(long, value_parser = parse_latitude, allow_hyphen_values = true)]
+		lat: f64,
+		/// Longitude (+ for E - for W, or a trailing E or W)
+		#[arg(long, value_parser = parse_longitude, allow_hyphen_values = true)]
+		lon: f64,
+	},
+	/// Forget the saved home position
+	ClearHome,
+	/// Check that the astronomy library, timezone database, terminal and clock work
+	Doctor,
+	/// Print the local mean sidereal time at a wall-clock time in the position's timezone
+	Convert {
+		/// Latitude (+ for N - for S, or a trailing N or S)
+		#[arg(long, value_parser = parse_latitude, allow_hyphen_values = true)]
+		lat: f64,
+		/// Longitude (+ for E - for W, or a trailing E or W)
+		#[arg(long, value_parser = parse_longitude, allow_hyphen_values = true)]
+		lon: f64,
+		/// Local wall-clock time, `HH:MM` or `HH:MM:SS`
+		#[arg(long, value_parser = parse_wall_clock_time)]
+		time: NaiveTime,
+		/// Local date, today in the position's timezone when left out
+		#[arg(long, value_name = "YYYY-MM-DD")]
+		date: Option<NaiveDate>,
+	},
+	/// Play back a file written by --log-csv through the display, from the logged values
+	Replay {
+		/// The CSV file to read
+		file: PathBuf,
+		/// How many times faster than it was logged to play the file back
+		#[arg(long, default_value_t = 1.0)]
+		speed: f64,
+	},
+	/// Print the timezone a position resolves to and roughly how far it is from a border with
+	/// another zone, without starting the display
+	CheckTimezone {
+		/// Latitude (+ for N - for S, or a trailing N or S)
+		#[arg(long, value_parser = parse_latitude, allow_hyphen_values = true)]
+		lat: f64,
+		/// Longitude (+ for E - for W, or a trailing E or W)
+		#[arg(long, value_parser = parse_longitude, allow_hyphen_values = true)]
+		lon: f64,
+		/// How far around the position to look for a border, in kilometres
+		#[arg(long, value_name = "KM", default_value_t = 25.0)]
+		radius: f64,
+		/// Warn that the zone may be unreliable when a border is closer than this, in kilometres
+		#[arg(long, value_name = "KM", default_value_t = 5.0)]
+		warn_within: f64,
+	},
+	/// Print every timezone the database has for a position, one per line
+	ListTimezones {
+		/// Latitude (+ for N - for S, or a trailing N or S)
+		#[arg(long, value_parser = parse_latitude, allow_hyphen_values = true)]
+		lat: f64,
+		/// Longitude (+ for E - for W, or a trailing E or W)
+		#[arg(long, value_parser = parse_longitude, allow_hyphen_values = true)]
+		lon: f64,
+	},
+}
+
+/// Outcome of one `sidtime doctor` check, with a hint on how to fix a failure.
+struct Check {
+	name: &'static str,
+	result: std::result::Result<String, String>,
+	hint: &'static str,
+}
+
+fn doctor_checks() -> Vec<Check> {
+	// GMST at 2000-01-01 12:00 UT, from the definition of the IAU 1982 expression.
+	const REFERENCE_GMST: f64 = 18.697_374_558;
+	const GMST_TOLERANCE_HOURS: f64 = 1.0 / 3600.0;
+
+	let reference = NaiveDate::from_ymd_opt(2000, 1, 1)
+		.and_then(|date| date.and_hms_opt(12, 0, 0))
+		.expect("valid reference date");
+	let libastro_gmst = greenwich_mean_sidereal_time(reference);
+	let libastro = if (libastro_gmst - REFERENCE_GMST).abs() < GMST_TOLERANCE_HOURS {
+		Ok(format!("GMST {libastro_gmst:.6}h at J2000.0"))
+	} else {
+		Err(format!(
+			"GMST {libastro_gmst}h at J2000.0, expected {REFERENCE_GMST}h"
+		))
+	};
+
+	let timezone = match get_timezone(51.4779, 0.0) {
+		Ok(timezone) if timezone.name() == "Europe/London" => {
+			Ok(format!("Greenwich is in {}", timezone.name()))
+		},
+		Ok(timezone) => Err(format!(
+			"Greenwich is in {}, expected Europe/London",
+			timezone.name()
+		)),
+		Err(e) => Err(e.to_string()),
+	};
+
+	let term = console::Term::stdout();
+	let terminal = if is_interactive(&term) {
+		let (rows, columns) = term.size();
+		Ok(format!(
+			"{columns}x{rows}, colors {}",
+			if console::colors_enabled() {
+				"on"
+			} else {
+				"off"
+			}
+		))
+	} else {
+		Err("stdout is not an interactive terminal".to_owned())
+	};
+
+	let now = Utc::now();
+	let clock = if (2023..2100).contains(&now.year()) {
+		Ok(now.format("%F %T UTC").to_string())
+	} else {
+		Err(format!("the clock reads {}", now.format("%F %T UTC")))
+	};
+
+	vec![
+		Check {
+			name: "libastro",
+			result: libastro,
+			hint: "rebuild libastro-sys for this platform",
+		},
+		Check {
+			name: "timezone database",
+			result: timezone,
+			hint: "rebuild with an up to date tzf-rs",
+		},
+		Check {
+			name: "terminal",
+			result: terminal,
+			hint: "the live display needs a terminal; use --format human-compact when piping",
+		},
+		Check {
+			name: "system clock",
+			result: clock,
+			hint: "set the system time, e.g. by enabling NTP",
+		},
+	]
+}
+
+fn run_doctor() -> Result<()> {
+	let checks = doctor_checks();
+	for check in &checks {
+		match &check.result {
+			Ok(detail) => println!("[PASS] {}: {detail}", check.name),
+			Err(detail) => println!(
+				"[FAIL] {}: {detail}\n       hint: {}",
+				check.name, check.hint
+			),
+		}
+	}
+	let failures = checks.iter().filter(|check| check.result.is_err()).count();
+	if failures == 0 {
+		Ok(())
+	} else {
+		Err(anyhow!("{failures} of {} checks failed", checks.len()))
+	}
+}
+
+/// Parse a positive duration written as a number with a unit of `ms`, `s`, `m` or `h`, or as a
+/// bare number of milliseconds.
+fn parse_interval(value: &str) -> std::result::Result<std::time::Duration, String> {
+	let split = value
+		.find(|c: char| c.is_ascii_alphabetic())
+		.unwrap_or(value.len());
+	let (number, unit) = value.split_at(split);
+	let seconds_per_unit = match unit.trim() {
+		"" | "ms" => 0.001,
+		"s" => 1.0,
+		"m" => 60.0,
+		"h" => 3600.0,
+		unit => return Err(format!("Unknown unit {unit:?}, expected ms, s, m or h")),
+	};
+	let number: f64 = number
+		.trim()
+		.parse()
+		.map_err(|e| format!("Bad duration {value:?}: {e}"))?;
+	match std::time::Duration::try_from_secs_f64(number * seconds_per_unit) {
+		Ok(duration) if !duration.is_zero() => Ok(duration),
+		_ => Err(format!("The duration {value:?} must be more than zero")),
+	}
+}
+
+fn parse_timezone(value: &str) -> std::result::Result<Tz, String> {
+	Tz::from_str(value).map_err(|e| format!("Unknown IANA timezone {value:?}: {e}"))
+}
+
+/// Parse an angle in decimal degrees, or in degrees, arcminutes and arcseconds the way
+/// [`format_angle`] writes them, e.g. `-12° 30′ 15″`. The units can also be written `d`, `'` and
+/// `"`, and any of them can be left out, as in `12° 30′`.
+fn parse_angle(text: &str) -> std::result::Result<f64, String> {
+	const UNITS: [(&[char], f64); 3] = [
+		(&['°', 'd'], 1.0),
+		(&['′', '\''], 60.0),
+		(&['″', '"'], 3600.0),
+	];
+	let decimal = match text.parse::<f64>() {
+		Ok(degrees) => return Ok(degrees),
+		Err(e) => e,
+	};
+	if !text.contains(|c: char| UNITS.iter().any(|(symbols, _)| symbols.contains(&c))) {
+		return Err(decimal.to_string());
+	}
+	let (sign, mut rest) = match text.strip_prefix('-') {
+		Some(rest) => (-1.0, rest),
+		None => (1.0, text.strip_prefix('+').unwrap_or(text)),
+	};
+	let mut degrees = 0.0;
+	for (index, (symbols, per_degree)) in UNITS.into_iter().enumerate() {
+		let Some((number, after)) = rest.split_once(symbols) else {
+			continue;
+		};
+		let number = number.trim();
+		let part = number
+			.parse::<f64>()
+			.map_err(|e| format!("{number:?} before {}: {e}", symbols[0]))?;
+		if part.is_sign_negative() || (index > 0 && part >= 60.0) {
+			return Err(format!(
+				"{number}{} is out of range, only the whole angle takes a sign and arcminutes and \
+				 arcseconds are under 60",
+				symbols[0]
+			));
+		}
+		degrees += part / per_degree;
+		rest = after;
+	}
+	if rest.trim().is_empty() {
+		Ok(sign * degrees)
+	} else {
+		Err(format!("unexpected {:?} after the last unit", rest.trim()))
+	}
+}
+
+/// Parse degrees with [`parse_angle`], where a trailing hemisphere letter may set the sign
+/// instead, e.g. `122.4W` for -122.4 or `37° 48′ N` for 37.8. A letter for the other axis is an
+/// error.
+fn parse_degrees(
+	value: &str,
+	axis: &str,
+	[positive, negative]: [char; 2],
+) -> std::result::Result<f64, String> {
+	let bad = |e: &dyn std::fmt::Display| format!("Bad {axis} {value:?}: {e}");
+	let (number, sign) = match value.chars().last().map(|c| c.to_ascii_uppercase()) {
+		Some(letter) if letter == positive => (&value[..value.len() - 1], 1.0),
+		Some(letter) if letter == negative => (&value[..value.len() - 1], -1.0),
+		Some(letter @ ('N' | 'S' | 'E' | 'W')) => {
+			return Err(bad(&format!(
+				"{letter} is not a hemisphere for a {axis}, use {positive} or {negative}"
+			)));
+		},
+		_ => return parse_angle(value).map_err(|e| bad(&e)),
+	};
+	let number = number.trim();
+	if number.starts_with(['-', '+']) {
+		return Err(bad(&"use either a sign or a hemisphere letter, not both"));
+	}
+	parse_angle(number)
+		.map(|degrees| sign * degrees)
+		.map_err(|e| bad(&e))
+}
+
+fn parse_latitude(value: &str) -> std::result::Result<f64, String> {
+	parse_degrees(value, "latitude", ['N', 'S'])
+}
+
+fn parse_longitude(value: &str) -> std::result::Result<f64, String> {
+	parse_degrees(value, "longitude", ['E', 'W'])
+}
+
+/// Parse a position written as `LAT,LON` in degrees.
+fn parse_coordinates(value: &str) -> std::result::Result<(f64, f64), String> {
+	let (latitude, longitude) = value
+		.split_once(',')
+		.ok_or_else(|| format!("Expected LAT,LON but got {value:?}"))?;
+	let latitude = parse_latitude(latitude.trim())?;
+	let longitude = parse_longitude(longitude.trim())?;
+	if !(-90.0..=90.0).contains(&latitude) {
+		return Err(format!("Latitude {latitude} is outside [-90, 90]"));
+	}
+	Ok((latitude, longitude))
+}
+
+/// Convert a coordinate given with --radians to degrees, checking it's within `[-limit, limit]`.
+fn radians_to_degrees(radians: f64, axis: &str, limit: f64) -> Result<f64> {
+	if !(-limit..=limit).contains(&radians) {
+		return Err(anyhow!(
+			"The {axis} {radians} is outside [-{limit:.6}, {limit:.6}] radians"
+		));
+	}
+	Ok(radians.to_degrees())
+}
+
+fn parse_wall_clock_time(value: &str) -> std::result::Result<NaiveTime, String> {
+	NaiveTime::parse_from_str(value, "%H:%M:%S")
+		.or_else(|_| NaiveTime::parse_from_str(value, "%H:%M"))
+		.map_err(|e| format!("Expected HH:MM or HH:MM:SS: {e}"))
+}
+
+/// The local mean sidereal time when the clocks at a position show `time` on `date`.
+///
+/// The wall-clock time is read in the zone's rules for that date, so daylight saving time is
+/// applied when it is in force. A time repeated when the clocks go back is reported twice, and a
+/// time skipped when they go forward is an error.
+fn convert_wall_clock(
+	latitude: f64,
+	longitude: f64,
+	time: NaiveTime,
+	date: Option<NaiveDate>,
+) -> Result<String> {
+	let timezone = get_timezone(latitude, longitude)?;
+	let date = date.unwrap_or_else(|| Utc::now().with_timezone(&timezone).date_naive());
+	let local = date.and_time(time);
+	let instants = match timezone.from_local_datetime(&local) {
+		LocalResult::Single(instant) => vec![instant],
+		LocalResult::Ambiguous(earliest, latest) => vec![earliest, latest],
+		LocalResult::None => {
+			return Err(anyhow!(
+				"{local} is skipped by the clock change in {}",
+				timezone.name()
+			))
+		},
+	};
+
+	let observer = Observer::new(Some(latitude), longitude);
+	let mut output = String::new();
+	for instant in instants {
+		let utc = instant.with_timezone(&Utc);
+		output.push_str(&format!(
+			"{:>32}: {}\n",
+			"Local Time",
+			instant.format("%F %T %z/%Z")
+		));
+		output.push_str(&format!(
+			"{:>32}: {}\n",
+			"Zone Meridian",
+			format_angle(zone_meridian(utc, timezone), AngleFormat::Decimal)
+		));
+		output.push_str(&format!(
+			"{:>32}: {}\n",
+			"Universal Time",
+			utc.format("%F %T")
+		));
+		output.push_str(&format!(
+			"{:>32}: {}\n",
+			"Local mean Sidereal Time",
+			decimal_to_time(observer.lmst(utc))?.format(TIME_FMT_STRING)
+		));
+	}
+	Ok(output)
+}
+
+/// The report of `sidtime check-timezone`.
+fn check_timezone(latitude: f64, longitude: f64, radius_km: f64, warn_within_km: f64) -> String {
+	let zone_name = |zone: Option<Tz>| zone.map_or("no single zone", |zone| zone.name());
+	let mut output = format!(
+		"{:>16}: {}\n",
+		"Zone",
+		match get_timezone(latitude, longitude) {
+			Ok(zone) => zone.name().to_owned(),
+			Err(e) => format!("unresolved ({e})"),
+		}
+	);
+	match nearest_timezone_border(latitude, longitude, radius_km) {
+		Some((distance, beyond)) => {
+			output.push_str(&format!(
+				"{:>16}: about {distance:.1} km, {} beyond it\n",
+				"Nearest border",
+				zone_name(beyond)
+			));
+			if distance <= warn_within_km {
+				output.push_str(&format!(
+					"Warning: within {warn_within_km} km of a border, so the zone may be wrong if \
+					 the position is off by that much\n"
+				));
+			}
+		},
+		None => output.push_str(&format!(
+			"{:>16}: none within {radius_km} km\n",
+			"Nearest border"
+		)),
+	}
+	output
+}
+
+fn run_command(command: &Command) -> Result<()> {
+	match *command {
+		Command::SetHome { lat, lon } => {
+			let mut config = Config::load()?;
+			config.home = Some(Home {
+				latitude: lat,
+				longitude: lon,
+			});
+			config.save()?;
+			println!("Saved home {lat}, {lon} to {}", Config::path()?.display());
+		},
+		Command::ClearHome => {
+			let mut config = Config::load()?;
+			config.home = None;
+			config.save()?;
+			println!("Cleared home from {}", Config::path()?.display());
+		},
+		Command::Doctor => run_doctor()?,
+		Command::Convert {
+			lat,
+			lon,
+			time,
+			date,
+		} => print!("{}", convert_wall_clock(lat, lon, time, date)?),
+		Command::Replay { ref file, speed } => replay(file, speed)?,
+		Command::CheckTimezone {
+			lat,
+			lon,
+			radius,
+			warn_within,
+		} => print!("{}", check_timezone(lat, lon, radius, warn_within)),
+		Command::ListTimezones { lat, lon } => {
+			let candidates = timezone_candidates(lat, lon);
+			if candidates.is_empty() {
+				println!("none");
+			}
+			for name in candidates {
+				println!("{name}");
+			}
+		},
+	}
+	Ok(())
+}
+
+/// Prints the libastro call statistics for --profile when dropped, which covers every way out of
+/// `main`.
+struct ProfileReport;
+
+impl Drop for ProfileReport {
+	fn drop(&mut self) {
+		for (name, statistics) in profile::statistics() {
+			eprintln!(
+				"{name:>16}: {} calls, min {:?}, max {:?}, mean {:?}",
+				statistics.count,
+				statistics.min,
+				statistics.max,
+				statistics.mean()
+			);
+		}
+	}
+}
+
+/// Print how long each step of startup took for --benchmark-startup, building the timezone
+/// finder on its own since the lookups otherwise hide it inside the first report.
+fn benchmark_startup(
+	parsing: std::time::Duration,
+	utc: DateTime<Utc>,
+	settings: &ReportSettings,
+	options: &DisplayOptions,
+) -> Result<()> {
+	let started = Instant::now();
+	let finder = tzf_rs::DefaultFinder::new();
+	let construction = started.elapsed();
+	drop(finder);
+
+	let started = Instant::now();
+	render_once(utc, settings, options)?;
+	let first_report = started.elapsed();
+
+	for (step, elapsed) in [
+		("Timezone finder construction", construction),
+		("Argument parsing", parsing),
+		("First report", first_report),
+	] {
+		println!("{step:>32}: {:.3} ms", elapsed.as_secs_f64() * 1000.0);
+	}
+	Ok(())
+}
+
+/// The objects given with --object-ra and --object-dec.
+fn objects(cli: &Cli) -> Result<Vec<Equatorial>> {
+	if cli.object_ra.len() != cli.object_dec.len() {
+		return Err(anyhow!(
+			"Got {} --object-ra but {} --object-dec, each object needs both",
+			cli.object_ra.len(),
+			cli.object_dec.len()
+		));
+	}
+	Ok(cli
+		.object_ra
+		.iter()
+		.zip(&cli.object_dec)
+		.map(|(&right_ascension, &declination)| Equatorial {
+			right_ascension,
+			declination,
+		})
+		.collect())
+}
+
+/// The report settings and display options the arguments ask for, with the saved home as the
+/// position when none is given.
+fn configure(cli: &Cli) -> Result<(ReportSettings, DisplayOptions)> {
+	let (lat, lon) = if cli.radians {
+		(
+			cli.lat
+				.map(|lat| radians_to_degrees(lat, "latitude", std::f64::consts::FRAC_PI_2))
+				.transpose()?,
+			cli.lon
+				.map(|lon| radians_to_degrees(lon, "longitude", std::f64::consts::PI))
+				.transpose()?,
+		)
+	} else {
+		(cli.lat, cli.lon)
+	};
+
+	let (latitude, longitude) = match lon {
+		None if !cli.midpoint_of.is_empty() => {
+			let midpoint = great_circle_midpoint(cli.midpoint_of[0], cli.midpoint_of[1])
+				.ok_or_else(|| anyhow!("The positions for --midpoint-of are antipodal"))?;
+			(Some(midpoint.0), midpoint.1)
+		},
+		Some(longitude) if cli.lon_west_positive => (lat, -longitude),
+		Some(longitude) => (lat, longitude),
+		// Falling back to the home position would quietly drop the latitude that was given.
+		None if lat.is_some() => {
+			return Err(anyhow!(
+				"--lat requires --lon, a latitude on its own doesn't fix a position"
+			));
+		},
+		None => {
+			let home = Config::load()?.home.ok_or_else(|| {
+				anyhow!(
+					"No coordinates given, pass a longitude with --lon or save a home with \
+					 `sidtime set-home`"
+				)
+			})?;
+			(Some(home.latitude), home.longitude)
+		},
+	};
+
+	let objects = objects(cli)?;
+	// A single object is tracked live, several get a transit table instead.
+	let object = match objects.as_slice() {
+		[object] => Some(*object),
+		_ => None,
+	};
+
+	let settings = ReportSettings {
+		observer: Observer {
+			latitude,
+			longitude,
+			elevation: cli.elevation,
+			body: cli.body,
+			algorithm: cli.algorithm,
+			atmosphere: cli.refraction.then_some(Atmosphere {
+				temperature: cli.temperature,
+				pressure: cli.pressure,
+			}),
+		},
+		object,
+		spotiswoode_peak: !cli.no_peak,
+		geocentric_latitude: cli.geocentric_lat,
+		events: cli
+			.events_file
+			.as_deref()
+			.map(load_events)
+			.transpose()?
+			.unwrap_or_default(),
+		sites: cli
+			.longitudes_file
+			.as_deref()
+			.map(|path| load_sites(path, cli.sort_longitudes))
+			.transpose()?
+			.unwrap_or_default(),
+	};
+
+	#[cfg(unix)]
+	let refresh_on_signal = cli.refresh_on_signal;
+	#[cfg(not(unix))]
+	let refresh_on_signal = false;
+
+	let options = DisplayOptions {
+		start_paused: cli.start_paused,
+		ascii_only: cli.ascii_only,
+		format: cli.format,
+		json_pretty: cli.json_pretty,
+		reverse_peak: cli.reverse_peak,
+		max_iterations: cli.max_iterations,
+		horizon_altitude: cli.horizon_altitude,
+		high_altitude: cli.high_altitude,
+		gmst_at_0h: cli.gmst_at_0h,
+		refresh_on_signal,
+		angle_format: cli.angle_format,
+		locale: cli.locale.unwrap_or_else(Locale::from_env),
+		align: cli.align,
+		show_drift: cli.show_drift,
+		summary_on_exit: cli.summary_on_exit,
+		profile: cli.profile,
+		two_column: cli.two_column,
+		wrap_width: cli.wrap_width,
+		time_notation: cli.time_notation,
+		precision: cli.precision.unwrap_or_default(),
+		utc_only: cli.utc_only,
+		assume_tz: cli.assume_tz,
+		output_timezone: cli.output_timezone,
+		freeze_date: cli.freeze_date,
+		until_midnight: cli.refresh_until_midnight,
+		interval: cli.interval,
+		fps: cli.fps,
+		countdown_style: cli.countdown_style,
+		peak_imminent: cli
+			.color_scale_peak
+			.then(|| Duration::minutes(i64::from(cli.peak_imminent))),
+		notify_within: cli.notify_within.map(Duration::from_std).transpose()?,
+		notify_desktop: cli.notify_desktop,
+		log_csv: cli.log_csv.clone(),
+		nautical_zone: cli.nautical_zone,
+	};
+
+	Ok((settings, options))
+}
+
+fn main() -> Result<()> {
+	let started = Instant::now();
+	let cli = Cli::parse();
+	let parsing = started.elapsed();
+
+	let _profile = cli.profile.then(|| {
+		profile::enable();
+		ProfileReport
+	});
+
+	if let Some(command) = &cli.command {
+		return run_command(command);
+	}
+
+	let (settings, options) = configure(&cli)?;
+	let objects = objects(&cli)?;
+
+	// Color codes would end up as garbage in the file, or on a console that can't show them.
+	if cli.no_color || cli.output.is_some() || !enable_ansi() {
+		console::set_colors_enabled(false);
+	}
+
+	if cli.strict {
+		check_strict(&settings, try_resolve_timezone(&settings, &options))?;
+	}
+
+	let clock: Box<dyn Clock> = match options.freeze_date {
+		Some(date) => Box::new(FrozenDateClock(date)),
+		None => Box::new(SystemClock),
+	};
+
+	if cli.dump_args {
+		print!("{}", dump_settings(&settings, &options));
+		return Ok(());
+	}
+
+	if cli.debug_ffi {
+		let now = cli.unix.unwrap_or_else(|| clock.now());
+		eprint!("UTC {}\n{}", now.to_rfc3339(), trace_ffi(now.naive_utc()));
+	}
+
+	if cli.benchmark_startup {
+		let now = cli.unix.unwrap_or_else(|| clock.now());
+		return benchmark_startup(parsing, now, &settings, &options);
+	}
+
+	let catalog = match &cli.catalog {
+		Some(path) => Some(load_catalog(path, cli.mag_limit)?),
+		None if objects.len() > 1 => Some(
+			objects
+				.iter()
+				.enumerate()
+				.map(|(index, position)| CatalogObject {
+					name: (index + 1).to_string(),
+					position: *position,
+					magnitude: None,
+				})
+				.collect(),
+		),
+		None => None,
+	};
+	if let Some(catalog) = catalog {
+		let timezone = options
+			.output_timezone
+			.or_else(|| resolve_timezone(&settings, &options));
+		let window = cli.catalog.is_some().then(|| hours_to_duration(cli.window));
+		println!(
+			"{}",
+			render_transit_table(
+				clock.now(),
+				&settings.observer,
+				&catalog,
+				window,
+				timezone,
+				&options
+			)?
+		);
+		return Ok(());
+	}
+
+	if cli.explain {
+		let text = explain(cli.unix.unwrap_or_else(|| clock.now()), &settings)?;
+		return write_output(cli.output.as_deref(), &text);
+	}
+
+	if let Some(utc) = cli.unix {
+		return write_output(
+			cli.output.as_deref(),
+			&render_once(utc, &settings, &options)?,
+		);
+	}
+
+	if cli.status {
+		let report = SiderealReport::new(
+			clock.now(),
+			&settings,
+			resolve_timezone(&settings, &options),
+		)?;
+		return write_output(
+			cli.output.as_deref(),
+			&render_template(&cli.template, &report, civil_timezone(&report, &options))?,
+		);
+	}
+
+	if !cli.sample_at.is_empty() {
+		// Parse everything first so a typo in the last instant doesn't leave half the output.
+		let samples = cli
+			.sample_at
+			.iter()
+			.enumerate()
+			.map(|(index, value)| {
+				parse_datetime(value)
+					.map_err(|e| anyhow!("--sample-at number {} ({value:?}): {e}", index + 1))
+			})
+			.collect::<Result<Vec<_>>>()?;
+		let reports = samples
+			.into_iter()
+			.map(|utc| render_once(utc, &settings, &options))
+			.collect::<Result<Vec<_>>>()?;
+		// Compact reports are one line each already; text reports need a blank line between them.
+		let separator = match options.format {
+			OutputFormat::Text => "\n\n",
+			OutputFormat::HumanCompact | OutputFormat::Json => "\n",
+		};
+		return write_output(cli.output.as_deref(), &reports.join(separator));
+	}
+
+	display_info(&settings, &options, clock.as_ref())
+}
+
+#[cfg(test)]
+mod tests {
+	use proptest::{prelude::any, prop_assert, prop_assert_eq, proptest};
+	use sidereal_time_calculator::{
+		clock::FixedClock,
+		timezone::{lookup_timezone, TimezoneFinder},
+	};
+
+	use super::*;
+
+	/// The settings and options for the arguments that follow the program name.
+	fn configured(args: &[&str]) -> (ReportSettings, DisplayOptions) {
+		let cli = Cli::try_parse_from(std::iter::once("sidtime").chain(args.iter().copied()))
+			.expect("valid arguments");
+		configure(&cli).expect("valid configuration")
+	}
+
+	fn instant(value: &str) -> DateTime<Utc> {
+		parse_datetime(value).expect("valid instant")
+	}
+
+	fn hms(hour: u32, minute: u32, second: u32) -> NaiveTime {
+		NaiveTime::from_hms_opt(hour, minute, second).expect("valid time")
+	}
+
+	#[test]
+	fn decimal_to_time_carries_whole_minutes_and_hours() {
+		// Each is within a nanosecond of the next minute, hour or day, where taking the fraction
+		// of each unit in turn used to come out as 60 seconds or 60 minutes.
+		for (hours, expected) in [
+			(
+				10.0 + 29.0 / 60.0 + 59.999_999_999_9 / 3600.0,
+				hms(10, 30, 0),
+			),
+			(0.999_999_999_999_99, hms(1, 0, 0)),
+			(23.999_999_999_999_99, hms(0, 0, 0)),
+			(24.0, hms(0, 0, 0)),
+		] {
+			assert_eq!(
+				decimal_to_time(hours).expect("in range"),
+				expected,
+				"{hours}"
+			);
+		}
+	}
+
+	/// The conversion `decimal_to_time` replaced, taking the fraction of each unit in turn.
+	#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+	fn decimal_to_time_by_fractions(hours: f64) -> NaiveTime {
+		let minutes = hours.fract() * 60.0;
+		let seconds = minutes.fract() * 60.0;
+		let nanos = seconds.fract() * 1e9;
+		NaiveTime::from_hms_nano_opt(hours as u32, minutes as u32, seconds as u32, nanos as u32)
+			.expect("valid time")
+	}
+
+	#[test]
+	fn decimal_to_time_keeps_every_microsecond() {
+		let microseconds = |time: NaiveTime| {
+			i64::from(time.num_seconds_from_midnight()) * 1_000_000
+				+ i64::from(time.nanosecond() / 1000)
+		};
+		let mut old_error = 0;
+		for (hour, minute, second, micros) in [
+			(6, 30, 0, 500_000),
+			(9, 15, 0, 250_000),
+			(18, 41, 50, 548_410),
+			(2, 7, 33, 333_333),
+			(1, 2, 3, 456_789),
+		] {
+			let expected =
+				NaiveTime::from_hms_micro_opt(hour, minute, second, micros).expect("valid time");
+			let hours = f64::from(hour)
+				+ f64::from(minute) / 60.0
+				+ (f64::from(second) + f64::from(micros) / 1e6) / 3600.0;
+			let new = decimal_to_time(hours).expect("in range");
+			assert_eq!(microseconds(new), microseconds(expected), "{expected}");
+			old_error +=
+				(microseconds(decimal_to_time_by_fractions(hours)) - microseconds(expected)).abs();
+		}
+		// The old way lost a microsecond on most of these.
+		assert!(old_error >= 3, "{old_error}");
+	}
+
+	proptest! {
+		#[test]
+		fn decimal_to_time_accepts_exactly_one_day(hours in any::<f64>()) {
+			prop_assert_eq!(decimal_to_time(hours).is_ok(), (0.0..=24.0).contains(&hours));
+		}
+
+		#[test]
+		fn degrees_round_trip_through_their_text(degrees in -90.0..=90.0_f64) {
+			prop_assert_eq!(parse_latitude(&degrees.to_string()), Ok(degrees));
+			let [north, east] = if degrees < 0.0 { ['S', 'W'] } else { ['N', 'E'] };
+			let magnitude = degrees.abs();
+			prop_assert_eq!(parse_latitude(&format!("{magnitude}{north}")), Ok(degrees));
+			prop_assert_eq!(parse_longitude(&format!("{magnitude}{east}")), Ok(degrees));
+		}
+
+		#[test]
+		fn sexagesimal_angles_round_trip_through_parse_and_format(degrees in -180.0..=180.0_f64) {
+			// The text is rounded to the arcsecond, and parsing it gives back that rounded angle.
+			let text = format_angle(degrees, AngleFormat::Sexagesimal);
+			let parsed = parse_longitude(&text).expect("formatted angles parse");
+			prop_assert!((parsed - degrees).abs() <= 0.5 / 3600.0 + 1e-9, "{} from {}", parsed, text);
+			prop_assert_eq!(format_angle(parsed, AngleFormat::Sexagesimal), text);
+		}
+	}
+
+	/// A fresh path under the system's temporary directory.
+	fn scratch_file(name: &str) -> PathBuf {
+		std::env::temp_dir().join(format!("sidtime-{}-{name}", std::process::id()))
+	}
+
+	#[test]
+	fn csv_log_numbers_rows_under_one_header() {
+		let (settings, _) = configured(&["--lon", "0"]);
+		let path = scratch_file("log.csv");
+		let mut logger = CsvLogger::create(&path).expect("created");
+		for second in 0..3 {
+			let utc = instant("2023-06-01T00:00:00Z") + Duration::seconds(second);
+			logger
+				.log(&SiderealReport::new(utc, &settings, None).expect("report"))
+				.expect("logged");
+		}
+		drop(logger);
+		let text = fs::read_to_string(&path).expect("log written");
+		let _ = fs::remove_file(&path);
+
+		let lines = text.lines().collect::<Vec<_>>();
+		assert_eq!(
+			lines[0],
+			"sequence,utc,monotonic_seconds,mjd,gmst_hours,lmst_hours"
+		);
+		assert_eq!(lines.len(), 4, "{text}");
+		for (sequence, line) in lines[1..].iter().enumerate() {
+			assert!(line.starts_with(&format!("{sequence},")), "{line}");
+			assert!(LoggedRow::parse(line).is_ok(), "{line}");
+		}
+	}
+
+	#[test]
+	fn identical_frames_are_written_once() {
+		let mut on_screen = OnScreen::default();
+		let mut writes = Vec::new();
+		for frame in ["a", "a", "a", "b", "b", "a"] {
+			if on_screen.needs(frame) {
+				writes.push(frame);
+				on_screen.show(frame.to_owned());
+			}
+		}
+		assert_eq!(writes, ["a", "b", "a"]);
+
+		// After the screen is cleared the same frame has to be drawn again.
+		on_screen.clear();
+		assert!(on_screen.needs("a"));
+	}
+
+	#[test]
+	fn nautical_zones_round_away_from_greenwich_on_the_boundary() {
+		for (longitude, offset) in [
+			(7.49, 0),
+			(7.5, 1),
+			(7.51, 1),
+			(-7.49, 0),
+			(-7.5, -1),
+			(-7.51, -1),
+			(172.5, 12),
+			(-172.5, -12),
+		] {
+			assert_eq!(nautical_offset_hours(longitude), offset, "{longitude}");
+		}
+	}
+
+	#[test]
+	fn west_positive_longitude_mirrors_the_lmst_about_greenwich() {
+		let at = instant("2024-03-01T04:00:00Z");
+		let report = |args: &[&str]| {
+			let (settings, _) = configured(args);
+			SiderealReport::new(at, &settings, None).expect("report")
+		};
+		let east = report(&["--lon", "75"]);
+		let west = report(&["--lon", "75", "--lon-west-positive"]);
+		assert_eq!(west.longitude, -75.0);
+		assert_eq!(west.local_mst, report(&["--lon", "-75"]).local_mst);
+		// 5h ahead of Greenwich one way and 5h behind it the other.
+		let gap = normalize_hours(east.local_mst + west.local_mst - 2.0 * east.greenwich_mst);
+		assert!(gap.min(24.0 - gap) < 1e-9, "{gap}");
+	}
+
+	#[test]
+	fn templates_fill_in_placeholders_and_escapes() {
+		let (settings, _) = configured(&["--lon", "0"]);
+		let report =
+			SiderealReport::new(instant("2000-01-01T12:00:00Z"), &settings, None).expect("report");
+		let filled = render_template(
+			"{{lmst}} {lmst}, {gmst} at {local} MJD {mjd}",
+			&report,
+			Some(Tz::Asia__Tokyo),
+		)
+		.expect("valid template");
+		assert_eq!(
+			filled,
+			format!(
+				"{{lmst}} 18:41:50, 18:41:50 at 21:00:00 MJD {:.5}",
+				report.mjd
+			)
+		);
+		assert_eq!(
+			render_template("{local} }}", &report, None).expect("valid template"),
+			"12:00:00 }"
+		);
+		assert!(render_template("{local} }", &report, None).is_err());
+		assert!(render_template("{lst}", &report, None).is_err());
+		assert!(render_template("{lmst", &report, None).is_err());
+	}
+
+	#[test]
+	fn countdowns_near_a_full_day_stay_in_range() {
+		let almost_a_day = Duration::days(1) - Duration::nanoseconds(1);
+		assert_eq!(
+			format_countdown(almost_a_day, CountdownStyle::Wrap),
+			"23:59:59.999999"
+		);
+		assert_eq!(
+			format_countdown(Duration::days(1), CountdownStyle::Wrap),
+			"00:00:00.000000"
+		);
+	}
+
+	#[test]
+	fn just_past_the_peak_counts_to_tomorrow_or_back() {
+		let countdown = PeakCountdown::new(13.5 + 1.0 / 3600.0);
+		assert_eq!(countdown.since, Duration::seconds(1));
+		assert_eq!(
+			format_countdown(countdown.until, CountdownStyle::Wrap),
+			"23:59:59.000000"
+		);
+		assert_eq!(
+			format_countdown(countdown.until, CountdownStyle::Signed),
+			"-00:00:01.000000"
+		);
+	}
+
+	#[test]
+	fn sidereal_day_of_year_counts_from_january_first() {
+		let (settings, _) = configured(&["--lon", "0"]);
+		let report =
+			SiderealReport::new(instant("2023-07-01T12:00:00Z"), &settings, None).expect("report");
+		// 181.5 solar days into 2023, times 1.002737909350795 sidereal days each.
+		let sidereal_days = report.sidereal_day_of_year.expect("computed for the Earth");
+		assert!(
+			(sidereal_days - 181.996_930_547).abs() < 1e-6,
+			"{sidereal_days}"
+		);
+	}
+
+	#[test]
+	fn fixed_clock_renders_the_rows_for_its_instant() {
+		let (settings, options) = configured(&["--lon", "0", "--locale", "en"]);
+		let clock = FixedClock(instant("2000-01-01T12:00:00Z"));
+		let rendered = render_once(clock.now(), &settings, &options).expect("rendered");
+		let rows = rendered.lines().collect::<Vec<_>>();
+		let row = |label: &str, value: &str| format!("{label:>32}: {value}");
+		for expected in [
+			row("Longitude", "  0.0"),
+			row("Gregorian Date", "2000-01-01"),
+			row("Universal Time", "12:00:00.000000 +0000/UTC"),
+		] {
+			assert!(
+				rows.contains(&expected.as_str()),
+				"{expected:?} in\n{rendered}"
+			);
+		}
+		// 18h 41m 50.548s at J2000, to within the differences between the models.
+		for label in ["Greenwich mean Sidereal Time", "Local mean Sidereal Time"] {
+			let prefix = row(label, "18:41:50.5");
+			assert!(
+				rows.iter().any(|row| row.starts_with(&prefix)),
+				"{prefix:?} in\n{rendered}"
+			);
+		}
+	}
+
+	/// A clock that moves on by a fixed step each time it is read.
+	struct SteppingClock {
+		next: std::cell::Cell<DateTime<Utc>>,
+		step: Duration,
+	}
+
+	impl Clock for SteppingClock {
+		fn now(&self) -> DateTime<Utc> {
+			self.next.replace(self.next.get() + self.step)
+		}
+	}
+
+	#[test]
+	fn watch_stops_when_the_clock_crosses_midnight() {
+		// How many readings of a clock stepping by 30 s from the start are shown before the watch
+		// ends.
+		let shown = |start: &str, boundary: DayBoundary, timezone: Option<Tz>| {
+			let clock = SteppingClock {
+				next: std::cell::Cell::new(instant(start)),
+				step: Duration::seconds(30),
+			};
+			let mut watch = DayWatch::new(boundary);
+			std::iter::repeat_with(|| clock.now())
+				.take(100)
+				.take_while(|now| !watch.crossed(*now, timezone))
+				.count()
+		};
+		// 23:59:00 and 23:59:30 are shown, and midnight ends the watch.
+		assert_eq!(shown("2024-03-01T23:59:00Z", DayBoundary::Utc, None), 2);
+		// Midnight in Tokyo is 15:00 UTC, and the UTC date doesn't matter.
+		let tokyo = Some(Tz::Asia__Tokyo);
+		assert_eq!(shown("2024-03-01T14:59:00Z", DayBoundary::Local, tokyo), 2);
+		assert_eq!(
+			shown("2024-03-01T23:59:00Z", DayBoundary::Local, tokyo),
+			100
+		);
+	}
+
+	#[test]
+	fn local_day_boundary_follows_the_zone() {
+		let utc = instant("2024-03-01T22:30:00Z");
+		let tokyo = Some(Tz::Asia__Tokyo);
+		let march = |day| NaiveDate::from_ymd_opt(2024, 3, day).expect("valid date");
+		assert_eq!(DayBoundary::Local.date(utc, tokyo), march(2));
+		assert_eq!(DayBoundary::Utc.date(utc, tokyo), march(1));
+		assert_eq!(DayBoundary::Local.date(utc, None), march(1));
+	}
+
+	#[test]
+	fn repeated_timezone_failures_warn_once_per_interval() {
+		let mut warning = Throttle::new(TIMEZONE_WARNING_INTERVAL);
+		let warnings = (0..100).filter(|_| warning.ready()).count();
+		assert_eq!(warnings, 1);
+
+		let mut warning = Throttle::new(std::time::Duration::from_millis(1));
+		assert!(warning.ready());
+		std::thread::sleep(std::time::Duration::from_millis(5));
+		assert!(warning.ready());
+	}
+
+	#[test]
+	fn sidereal_times_format_in_both_notations() {
+		let hours = 14.0 + 32.0 / 60.0 + 7.25 / 3600.0;
+		for (digits, colon, hms) in [
+			(0, "14:32:07", "14h 32m 07s"),
+			(2, "14:32:07.25", "14h 32m 07.25s"),
+		] {
+			assert_eq!(
+				format_sidereal_time(hours, digits, TimeNotation::Colon).expect("in range"),
+				colon
+			);
+			assert_eq!(
+				format_sidereal_time(hours, digits, TimeNotation::Hms).expect("in range"),
+				hms
+			);
+		}
+	}
+
+	#[test]
+	fn lines_longer_than_a_narrow_screen_take_several_rows() {
+		let line = |columns| "x".repeat(columns);
+		assert_eq!(screen_rows(&line(10), 10), 1);
+		assert_eq!(screen_rows(&line(11), 10), 2);
+		assert_eq!(screen_rows(&line(25), 10), 3);
+		assert_eq!(screen_rows(&format!("{}\n\n{}", line(3), line(21)), 10), 5);
+		assert_eq!(screen_rows(&line(25), 0), 1);
+		// Escape codes take no columns.
+		let red = format!("\x1b[31m{}\x1b[0m", line(10));
+		assert_eq!(screen_rows(&red, 10), 1);
+	}
+
+	#[test]
+	fn midpoint_is_a_position_of_its_own() {
+		let parse = |args: &[&str]| {
+			Cli::try_parse_from(std::iter::once("sidtime").chain(args.iter().copied()))
+		};
+		let midpoint = ["--midpoint-of", "0,170", "0,-170"];
+		assert!(parse(&midpoint).is_ok());
+		assert!(parse(&[&midpoint[..], &["--lon", "0"][..]].concat()).is_err());
+		assert!(parse(&[&midpoint[..], &["--lat", "0"][..]].concat()).is_err());
+		// A second pair of positions would be ignored, so it's refused instead.
+		assert!(parse(&[&midpoint[..], &midpoint[..]].concat()).is_err());
+	}
+
+	#[test]
+	fn pretty_json_is_indented_and_compact_json_is_one_line() {
+		let at = instant("2024-03-01T04:00:00Z");
+		let json = |args: &[&str]| {
+			let (settings, options) = configured(args);
+			render_once(at, &settings, &options).expect("rendered")
+		};
+		let compact = json(&["--lon", "0", "--format", "json"]);
+		let pretty = json(&["--lon", "0", "--format", "json", "--json-pretty"]);
+		assert!(!compact.contains('\n'), "{compact}");
+		assert!(pretty.contains("\n  \""), "{pretty}");
+		let parse = |text: &str| serde_json::from_str::<serde_json::Value>(text).expect("JSON");
+		assert_eq!(parse(&compact), parse(&pretty));
+	}
+
+	#[test]
+	fn hemisphere_letters_set_the_sign() {
+		assert_eq!(parse_latitude("51.5N"), Ok(51.5));
+		assert_eq!(parse_latitude("33.9S"), Ok(-33.9));
+		assert_eq!(parse_latitude("33.9 s"), Ok(-33.9));
+		assert_eq!(parse_longitude("151.2E"), Ok(151.2));
+		assert_eq!(parse_longitude("0.1w"), Ok(-0.1));
+		assert_eq!(parse_longitude("-0.1"), Ok(-0.1));
+		let wrong_axis = parse_latitude("10E").expect_err("E is for longitudes");
+		assert!(wrong_axis.contains("use N or S"), "{wrong_axis}");
+		assert!(parse_longitude("45N").is_err());
+		assert!(parse_longitude("-45W").is_err());
+	}
+
+	#[test]
+	fn hemisphere_letters_follow_degrees_minutes_and_seconds() {
+		let close = |parsed: std::result::Result<f64, String>, expected: f64| {
+			let parsed = parsed.expect("parses");
+			assert!(
+				(parsed - expected).abs() < 1e-12,
+				"{parsed}, expected {expected}"
+			);
+		};
+		close(parse_latitude("37° 48′ N"), 37.8);
+		close(
+			parse_latitude("33d 52' 4.8\" S"),
+			-(33.0 + 52.0 / 60.0 + 4.8 / 3600.0),
+		);
+		close(
+			parse_longitude("122°25′9.6″W"),
+			-(122.0 + 25.0 / 60.0 + 9.6 / 3600.0),
+		);
+		close(parse_longitude("-0° 30′"), -0.5);
+		close(parse_longitude("151° 12′ E"), 151.2);
+		assert!(parse_longitude("45° 0′ N").is_err());
+		assert!(parse_latitude("-12° 30′ S").is_err());
+		assert!(parse_latitude("12° 60′ N").is_err());
+		assert!(parse_latitude("12° -5′").is_err());
+		assert!(parse_latitude("12° 30′ 15").is_err());
+	}
+
+	#[test]
+	fn intervals_parse_with_and_without_units() {
+		let millis = std::time::Duration::from_millis;
+		for (text, expected) in [
+			("250", millis(250)),
+			("500ms", millis(500)),
+			("2s", millis(2_000)),
+			("1.5 m", millis(90_000)),
+			("1h", millis(3_600_000)),
+		] {
+			assert_eq!(parse_interval(text), Ok(expected), "{text}");
+		}
+		for text in ["0s", "-1s", "5d", "s", "fast"] {
+			assert!(parse_interval(text).is_err(), "{text}");
+		}
+	}
+
+	#[test]
+	fn position_shows_the_digits_asked_for() {
+		let at = instant("2024-03-01T04:00:00Z");
+		let rendered = |args: &[&str]| {
+			let (settings, options) = configured(args);
+			render_once(at, &settings, &options).expect("rendered")
+		};
+		let longitude_only = rendered(&[
+			"--lon",
+			"-122.4194",
+			"--precision",
+			"coords=4",
+			"--locale",
+			"en",
+		]);
+		assert!(longitude_only.contains(": -122.4194\n"), "{longitude_only}");
+		let with_zone = rendered(&[
+			"--lat",
+			"37.7749",
+			"--lon",
+			"-122.4194",
+			"--precision",
+			"coords=4",
+			"--locale",
+			"en",
+		]);
+		assert!(
+			with_zone.contains("Zone for 37.7749, -122.4194: America/Los_Angeles"),
+			"{with_zone}"
+		);
+		let default = rendered(&["--lon", "-122.4194", "--locale", "en"]);
+		assert!(default.contains(": -122.4\n"), "{default}");
+	}
+
+	#[test]
+	fn sidereal_times_just_before_midnight_carry_to_00_00_00() {
+		let before_midnight = |seconds: f64| 24.0 - seconds / 3600.0;
+		for (hours, digits, expected) in [
+			(before_midnight(0.4), 0, "00:00:00"),
+			(before_midnight(0.6), 0, "23:59:59"),
+			(before_midnight(0.004), 2, "00:00:00.00"),
+			(before_midnight(0.006), 2, "23:59:59.99"),
+			(before_midnight(1e-7), 6, "00:00:00.000000"),
+		] {
+			assert!((0.0..24.0).contains(&round_hours(hours, digits)));
+			assert_eq!(
+				format_sidereal_time(hours, digits, TimeNotation::Colon).expect("in range"),
+				expected,
+				"{hours}"
+			);
+		}
+	}
+
+	#[test]
+	fn every_model_gives_about_the_same_sidereal_time() {
+		let at = instant("2024-03-01T04:00:00Z");
+		let gmst = |model: &str| {
+			let (settings, _) = configured(&["--lon", "0", "--model", model]);
+			SiderealReport::new(at, &settings, None)
+				.expect("report")
+				.greenwich_mst
+		};
+		let models = ["libastro", "iau1982", "iau2006"].map(|model| (model, gmst(model)));
+		// libastro's older coefficients keep it within a tenth of a second or so of the IAU
+		// models, which agree with each other to milliseconds.
+		for (a, first) in models {
+			for (b, second) in models {
+				let seconds = (first - second).abs() * 3600.0;
+				assert!(seconds < 0.2, "{a} and {b} differ by {seconds}s");
+			}
+		}
+	}
+
+	#[test]
+	fn vega_overhead_is_in_lyra() {
+		// Vega, at 18h 36.9m +38° 47′, is on the meridian at 2000-01-01 12:00 UT a little west of
+		// Greenwich.
+		let (settings, _) = configured(&["--lat", "38.78", "--lon", "-1.22"]);
+		let report =
+			SiderealReport::new(instant("2000-01-01T12:00:00Z"), &settings, None).expect("report");
+		let constellation = report.zenith_constellation.expect("latitude given");
+		assert_eq!(constellation.abbreviation, "Lyr");
+	}
+
+	#[test]
+	fn radians_convert_to_degrees() {
+		use std::f64::consts::{FRAC_PI_4, PI};
+		assert!((radians_to_degrees(PI, "longitude", PI).expect("in range") - 180.0).abs() < 1e-12);
+		assert!(radians_to_degrees(PI + 0.01, "longitude", PI).is_err());
+
+		let (latitude, longitude) = (FRAC_PI_4.to_string(), (-PI).to_string());
+		let (settings, _) = configured(&[
+			"--radians",
+			"--lat",
+			latitude.as_str(),
+			"--lon",
+			longitude.as_str(),
+		]);
+		let latitude = settings.observer.latitude.expect("latitude given");
+		assert!((latitude - 45.0).abs() < 1e-12, "{latitude}");
+		assert!((settings.observer.longitude + 180.0).abs() < 1e-12);
+	}
+
+	#[test]
+	fn civil_times_follow_the_output_timezone() {
+		let at = instant("2024-01-15T12:00:00Z");
+		let Some(DailyCrossing::RisesAndSets { rise, .. }) =
+			Observer::new(Some(51.5), 0.0).sun_rise_set(at.date_naive())
+		else {
+			panic!("the Sun rises in London in January");
+		};
+		// About 8 a.m. in London, which is 5 p.m. in Tokyo and 3 a.m. in New York.
+		assert!((7..=8).contains(&rise.hour()), "{rise}");
+		for (zone, local_time, offset) in [
+			("Asia/Tokyo", "21:00:00.000000 +0900/JST", "+0900/JST"),
+			("America/New_York", "07:00:00.000000 -0500/EST", "-0500/EST"),
+		] {
+			let (settings, options) = configured(&[
+				"--lat",
+				"51.5",
+				"--lon",
+				"0",
+				"--output-timezone",
+				zone,
+				"--locale",
+				"en",
+			]);
+			let rendered = render_once(at, &settings, &options).expect("rendered");
+			let rows = rendered.lines().collect::<Vec<_>>();
+			let row = |label: &str| {
+				rows.iter()
+					.find(|row| row.trim_start().starts_with(label))
+					.unwrap_or_else(|| panic!("no {label} row in\n{rendered}"))
+			};
+			assert!(row("Zone for").ends_with(": Europe/London"), "{rendered}");
+			assert!(row("Local Time").ends_with(local_time), "{rendered}");
+			assert!(row("Next Sidereal Midnight").contains(offset), "{rendered}");
+			let timezone = zone.parse::<Tz>().expect("valid zone");
+			let sunrise = format!(
+				"{} ({})",
+				format_zoned_time(&rise, 0),
+				format_zoned_time(&rise.with_timezone(&timezone), 0)
+			);
+			assert!(row("Sunrise").ends_with(&sunrise), "{rendered}");
+			assert!(row("Sunset").contains(offset), "{rendered}");
+		}
+	}
+
+	#[test]
+	fn assumed_zone_shows_the_local_time_without_a_latitude() {
+		let (settings, options) = configured(&[
+			"--assume-tz",
+			"Europe/Berlin",
+			"--lon",
+			"13.4",
+			"--locale",
+			"en",
+		]);
+		let rendered =
+			render_once(instant("2024-01-15T12:00:00Z"), &settings, &options).expect("rendered");
+		let local_time = format!("{:>32}: 13:00:00.000000 +0100/CET", "Local Time");
+		assert!(rendered.lines().any(|row| row == local_time), "{rendered}");
+		assert!(rendered.contains("Longitude Correction"), "{rendered}");
+	}
+
+	/// A finder that gives the same candidate zones everywhere, as near some borders.
+	struct Candidates(&'static [&'static str]);
+
+	impl TimezoneFinder for Candidates {
+		fn get_tz_names(&self, _: f64, _: f64) -> Vec<&str> {
+			self.0.to_vec()
+		}
+	}
+
+	#[test]
+	fn strict_mode_fails_on_an_ambiguous_timezone() {
+		let (settings, _) = configured(&["--strict", "--lat", "49.0", "--lon", "8.2"]);
+		let lookup = |finder: Candidates| Some(lookup_timezone(&finder, 49.0, 8.2));
+		let ambiguous = lookup(Candidates(&["Europe/Berlin", "Europe/Paris"]));
+		let error = check_strict(&settings, ambiguous).expect_err("two zones is an error");
+		assert!(error.to_string().starts_with("--strict"), "{error}");
+		assert!(check_strict(&settings, lookup(Candidates(&["Europe/Berlin"]))).is_ok());
+		assert!(check_strict(&settings, lookup(Candidates(&[]))).is_err());
+	}
+
+	#[test]
+	fn gmst_at_0h_matches_the_almanac() {
+		let (settings, _) = configured(&["--lon", "0"]);
+		let report =
+			SiderealReport::new(instant("2000-01-01T15:30:00Z"), &settings, None).expect("report");
+		// 6h 39m 52.2707s, from the Astronomical Almanac for 2000.
+		let expected = 6.0 + 39.0 / 60.0 + 52.2707 / 3600.0;
+		let gmst_at_0h = report.gmst_at_0h.expect("computed for the Earth");
+		assert!(
+			(gmst_at_0h - expected).abs() * 3600.0 < 0.2,
+			"{gmst_at_0h}h, expected {expected}h"
+		);
+	}
+}
@@ -0,0 +1,397 @@
+//! A place to observe from, with the sidereal and solar times seen there.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+use crate::{
+	astro::{
+		greenwich_mean_sidereal_time_with, horizontal_coordinates, local_apparent_solar_time,
+		local_mean_sidereal_time, prime_meridian_sidereal_time, sun_equatorial, Atmosphere, Body,
+		Equatorial, GmstAlgorithm, MARS_ROTATION, SIDEREAL_DAYS_PER_SOLAR_DAY,
+	},
+	time::{hours_to_duration, normalize_hours},
+};
+
+/// Altitude of the Sun's centre at sunrise and sunset on a sea level horizon, in degrees. It
+/// allows for the Sun's semi-diameter and the usual 34′ of refraction.
+const SUNRISE_ALTITUDE: f64 = -0.833;
+
+/// Altitude of the Sun's centre in degrees at the start and end of astronomical twilight.
+pub const ASTRONOMICAL_TWILIGHT: f64 = -18.0;
+
+/// Solar hours per sidereal hour.
+pub const SIDEREAL_TO_SOLAR: f64 = 1.0 / SIDEREAL_DAYS_PER_SOLAR_DAY;
+
+/// Where the Sun is relative to a given altitude over one day.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DailyCrossing {
+	/// The Sun rises through the altitude and later sets through it.
+	RisesAndSets {
+		rise: DateTime<Utc>,
+		set: DateTime<Utc>,
+	},
+	/// The Sun stays above the altitude all day, like the midnight sun.
+	AlwaysAbove,
+	/// The Sun stays below the altitude all day, like the polar night.
+	AlwaysBelow,
+}
+
+/// How long darkness lasts or how long until it falls, where dark means the Sun is below
+/// astronomical twilight.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Darkness {
+	/// It is dark, and morning twilight begins after this long.
+	Dark(Duration),
+	/// It is light, and astronomical dusk comes after this long.
+	Light(Duration),
+	/// It is dark and stays dark for at least the next two days.
+	AlwaysDark,
+	/// It is light and doesn't get dark in the next two days.
+	NeverDark,
+}
+
+/// An observer on a body's surface.
+///
+/// Longitude is in degrees east and elevation in metres above the surrounding horizon. Without a
+/// latitude, only the times that depend on longitude alone are available. Solar times are only
+/// computed for the Earth. With an atmosphere, the altitudes of objects are the apparent ones,
+/// lifted by refraction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Observer {
+	pub latitude: Option<f64>,
+	pub longitude: f64,
+	pub elevation: f64,
+	pub body: Body,
+	pub algorithm: GmstAlgorithm,
+	pub atmosphere: Option<Atmosphere>,
+}
+
+impl Observer {
+	/// An observer on the Earth at sea level, using libastro's sidereal time.
+	#[must_use]
+	pub const fn new(latitude: Option<f64>, longitude: f64) -> Self {
+		Self {
+			latitude,
+			longitude,
+			elevation: 0.0,
+			body: Body::Earth,
+			algorithm: GmstAlgorithm::Libastro,
+			atmosphere: None,
+		}
+	}
+
+	/// Sidereal time at the body's prime meridian in hours, which is GMST for the Earth.
+	#[must_use]
+	pub fn gmst(&self, at: DateTime<Utc>) -> f64 {
+		match self.body {
+			Body::Earth => greenwich_mean_sidereal_time_with(at.naive_utc(), self.algorithm),
+			Body::Mars => prime_meridian_sidereal_time(at.naive_utc(), &MARS_ROTATION),
+		}
+	}
+
+	/// Local mean sidereal time in hours.
+	#[must_use]
+	pub fn lmst(&self, at: DateTime<Utc>) -> f64 {
+		local_mean_sidereal_time(self.gmst(at), self.longitude)
+	}
+
+	/// Local apparent solar time in hours.
+	#[must_use]
+	pub fn apparent_solar_time(&self, at: DateTime<Utc>) -> Option<f64> {
+		(self.body == Body::Earth)
+			.then(|| local_apparent_solar_time(at.naive_utc(), self.longitude))
+	}
+
+	/// Hours from local apparent noon in `[-12, 12)`, negative before noon.
+	#[must_use]
+	pub fn solar_hour_angle(&self, at: DateTime<Utc>) -> Option<f64> {
+		(self.body == Body::Earth).then(|| self.sun_hour_angle(at))
+	}
+
+	/// Altitude and azimuth in degrees of an object given in Earth equatorial coordinates.
+	#[must_use]
+	pub fn horizontal(&self, at: DateTime<Utc>, object: Equatorial) -> Option<(f64, f64)> {
+		// Right ascension and declination are measured against the Earth's equator.
+		match self.latitude {
+			Some(latitude) if self.body == Body::Earth => {
+				let (altitude, azimuth) = horizontal_coordinates(
+					self.lmst(at) - object.right_ascension,
+					object.declination,
+					latitude,
+				);
+				Some((self.apparent_altitude(altitude), azimuth))
+			},
+			_ => None,
+		}
+	}
+
+	/// The equatorial coordinates overhead: the right ascension on the meridian, which is the
+	/// local sidereal time, and a declination equal to the latitude.
+	#[must_use]
+	pub fn zenith(&self, at: DateTime<Utc>) -> Option<Equatorial> {
+		let latitude = self.latitude.filter(|_| self.body == Body::Earth)?;
+		Some(Equatorial {
+			right_ascension: self.lmst(at),
+			declination: latitude,
+		})
+	}
+
+	/// The first time at or after `after` when the object crosses the local meridian, where the
+	/// local sidereal time equals its right ascension.
+	#[must_use]
+	pub fn next_transit(&self, after: DateTime<Utc>, object: Equatorial) -> Option<DateTime<Utc>> {
+		self.next_local_sidereal_time(after, object.right_ascension)
+	}
+
+	/// The first time at or after `after` when the local sidereal time reads `lst` hours.
+	/// Sidereal time gains about 3m56s a day on the clock, so this comes that much earlier each
+	/// day.
+	#[must_use]
+	pub fn next_local_sidereal_time(
+		&self,
+		after: DateTime<Utc>,
+		lst: f64,
+	) -> Option<DateTime<Utc>> {
+		(self.body == Body::Earth).then(|| {
+			let sidereal_hours = normalize_hours(lst - self.lmst(after));
+			after + hours_to_duration(sidereal_hours * SIDEREAL_TO_SOLAR)
+		})
+	}
+
+	/// The object's altitude in degrees as it crosses the meridian, its highest for the day.
+	#[must_use]
+	pub fn transit_altitude(&self, object: Equatorial) -> Option<f64> {
+		let latitude = self.latitude.filter(|_| self.body == Body::Earth)?;
+		Some(self.apparent_altitude(90.0 - (latitude - object.declination).abs()))
+	}
+
+	fn apparent_altitude(&self, altitude: f64) -> f64 {
+		altitude
+			+ self
+				.atmosphere
+				.map_or(0.0, |atmosphere| atmosphere.refraction(altitude))
+	}
+
+	/// The instant on the given UTC date when the Sun crosses the local meridian.
+	#[must_use]
+	pub fn solar_transit(&self, date: NaiveDate) -> Option<DateTime<Utc>> {
+		if self.body != Body::Earth {
+			return None;
+		}
+		let noon = date.and_hms_opt(12, 0, 0)?.and_utc();
+		let mut transit = noon - hours_to_duration(self.longitude / 15.0);
+		// The Sun's right ascension moves by about a degree a day, so three corrections settle well
+		// under a second.
+		for _ in 0..3 {
+			transit = transit - hours_to_duration(self.sun_hour_angle(transit) * SIDEREAL_TO_SOLAR);
+		}
+		Some(transit)
+	}
+
+	/// The local sidereal time when the Sun next or last crossed the meridian on the observer's
+	/// local day, which is the right ascension of the objects near the Sun. Only computed when the
+	/// latitude is known.
+	#[must_use]
+	pub fn lmst_at_solar_noon(&self, at: DateTime<Utc>) -> Option<f64> {
+		self.latitude?;
+		// The local mean solar date, so the transit found is the one within about 12 hours.
+		let date = (at + hours_to_duration(self.longitude / 15.0)).date_naive();
+		Some(self.lmst(self.solar_transit(date)?))
+	}
+
+	/// Sunrise and sunset around the solar transit on the given UTC date, for the upper limb on
+	/// the horizon. The horizon dips as the elevation grows, which brings sunrise earlier.
+	#[must_use]
+	pub fn sun_rise_set(&self, date: NaiveDate) -> Option<DailyCrossing> {
+		let dip = 0.0293 * self.elevation.max(0.0).sqrt();
+		self.sun_crossings(date, SUNRISE_ALTITUDE - dip)
+	}
+
+	/// When the Sun's centre passes through the given altitude in degrees, around the solar
+	/// transit on the given UTC date.
+	#[must_use]
+	pub fn sun_crossings(&self, date: NaiveDate, altitude: f64) -> Option<DailyCrossing> {
+		let latitude = self.latitude?;
+		let transit = self.solar_transit(date)?;
+		let mut events = [transit, transit];
+		for (event, sign) in events.iter_mut().zip([-1.0, 1.0]) {
+			// Refine the semi-diurnal arc with the declination at the previous estimate of the
+			// event, since it changes by up to 0.4° a day.
+			for _ in 0..3 {
+				let declination = sun_equatorial(event.naive_utc()).declination;
+				let half_arc = match semi_diurnal_arc(altitude, declination, latitude) {
+					Ok(hours) => hours,
+					Err(crossing) => return Some(crossing),
+				};
+				// The Sun's hour angle runs at about an hour per hour of clock time, unlike that
+				// of a star.
+				*event = transit + hours_to_duration(sign * half_arc);
+			}
+		}
+		let [rise, set] = events;
+		Some(DailyCrossing::RisesAndSets { rise, set })
+	}
+
+	/// The Sun's altitude in degrees.
+	#[must_use]
+	pub fn sun_altitude(&self, at: DateTime<Utc>) -> Option<f64> {
+		let latitude = self.latitude.filter(|_| self.body == Body::Earth)?;
+		let sun = sun_equatorial(at.naive_utc());
+		Some(horizontal_coordinates(self.sun_hour_angle(at), sun.declination, latitude).0)
+	}
+
+	/// Time left until the darkness ends, or until it begins when it is light.
+	#[must_use]
+	pub fn darkness(&self, at: DateTime<Utc>) -> Option<Darkness> {
+		let dark = self.sun_altitude(at)? < ASTRONOMICAL_TWILIGHT;
+		// Dawn is the Sun rising through twilight and dusk is it setting through it. The crossings
+		// are grouped by the day of their transit, so look from the day before.
+		let next = (-1..=2)
+			.filter_map(|days| {
+				let date = (at + Duration::days(days)).date_naive();
+				match self.sun_crossings(date, ASTRONOMICAL_TWILIGHT)? {
+					DailyCrossing::RisesAndSets { rise, set } => {
+						Some(if dark { rise } else { set })
+					},
+					DailyCrossing::AlwaysAbove | DailyCrossing::AlwaysBelow => None,
+				}
+			})
+			.filter(|event| *event > at)
+			.min();
+		Some(match (next, dark) {
+			(Some(event), true) => Darkness::Dark(event - at),
+			(Some(event), false) => Darkness::Light(event - at),
+			(None, true) => Darkness::AlwaysDark,
+			(None, false) => Darkness::NeverDark,
+		})
+	}
+
+	fn sun_hour_angle(&self, at: DateTime<Utc>) -> f64 {
+		let right_ascension = sun_equatorial(at.naive_utc()).right_ascension;
+		normalize_hours(self.lmst(at) - right_ascension + 12.0) - 12.0
+	}
+}
+
+/// The hour angle in hours from transit to the crossing of an altitude, or which side of the
+/// altitude the object stays when it never crosses.
+fn semi_diurnal_arc(altitude: f64, declination: f64, latitude: f64) -> Result<f64, DailyCrossing> {
+	let (h, dec, lat) = (
+		altitude.to_radians(),
+		declination.to_radians(),
+		latitude.to_radians(),
+	);
+	let cos_arc = (h.sin() - lat.sin() * dec.sin()) / (lat.cos() * dec.cos());
+	if cos_arc < -1.0 {
+		Err(DailyCrossing::AlwaysAbove)
+	} else if cos_arc > 1.0 {
+		Err(DailyCrossing::AlwaysBelow)
+	} else {
+		Ok(cos_arc.acos().to_degrees() / 15.0)
+	}
+}
+
+/// The point halfway along the great circle between two positions, as latitude and longitude in
+/// degrees with the longitude in `[-180, 180)`. There is no single midpoint between antipodes.
+#[must_use]
+pub fn great_circle_midpoint(a: (f64, f64), b: (f64, f64)) -> Option<(f64, f64)> {
+	let (lat1, lon1) = (a.0.to_radians(), a.1.to_radians());
+	let (lat2, lon2) = (b.0.to_radians(), b.1.to_radians());
+	// Add the two unit vectors in a frame turned so the first point is at longitude 0, which keeps
+	// the sum the same whichever way round the antimeridian the points are.
+	let x = lat1.cos() + lat2.cos() * (lon2 - lon1).cos();
+	let y = lat2.cos() * (lon2 - lon1).sin();
+	let z = lat1.sin() + lat2.sin();
+	let horizontal = x.hypot(y);
+	if horizontal.hypot(z) < 1e-12 {
+		return None;
+	}
+	let latitude = z.atan2(horizontal).to_degrees();
+	let longitude = (lon1.to_degrees() + y.atan2(x).to_degrees() + 180.0).rem_euclid(360.0) - 180.0;
+	Some((latitude, longitude))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+		NaiveDate::from_ymd_opt(year, month, day).expect("valid date")
+	}
+
+	#[test]
+	fn sun_is_at_the_altitude_at_its_crossings() {
+		for (latitude, when) in [
+			(0.0, date(2023, 3, 20)),
+			(40.0, date(2023, 6, 21)),
+			(40.0, date(2023, 12, 21)),
+			(60.0, date(2023, 6, 1)),
+		] {
+			let observer = Observer::new(Some(latitude), -75.0);
+			for altitude in [SUNRISE_ALTITUDE, ASTRONOMICAL_TWILIGHT] {
+				let Some(DailyCrossing::RisesAndSets { rise, set }) =
+					observer.sun_crossings(when, altitude)
+				else {
+					continue;
+				};
+				for event in [rise, set] {
+					let found = observer.sun_altitude(event).expect("latitude given");
+					assert!(
+						(found - altitude).abs() < 0.05,
+						"{latitude}° on {when}: {found}° at {event}, expected {altitude}°"
+					);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn midpoint_across_the_antimeridian_stays_on_it() {
+		for (a, b) in [
+			((0.0, 170.0), (0.0, -170.0)),
+			((10.0, 179.0), (-10.0, -179.0)),
+		] {
+			let (latitude, longitude) = great_circle_midpoint(a, b).expect("not antipodal");
+			assert!(latitude.abs() < 1e-9, "{latitude}");
+			assert!((longitude.abs() - 180.0).abs() < 1e-9, "{longitude}");
+		}
+		assert!(great_circle_midpoint((0.0, 0.0), (0.0, 180.0)).is_none());
+	}
+
+	#[test]
+	fn lmst_at_solar_noon_is_the_suns_right_ascension() {
+		// The Sun crossed the equator at 03:06 UT on 2024-03-20 and was 12h round the sky half a
+		// year later, so objects near it were about 0h and 12h of right ascension.
+		for (longitude, noon, expected) in [
+			(0.0, "2024-03-20T12:00:00Z", 0.0),
+			(-75.0, "2024-03-20T17:00:00Z", 0.0),
+			(139.7, "2024-09-22T03:00:00Z", 12.0),
+		] {
+			let at = noon.parse::<DateTime<Utc>>().expect("valid instant");
+			let lmst = Observer::new(Some(40.0), longitude)
+				.lmst_at_solar_noon(at)
+				.expect("latitude given");
+			let minutes = normalize_hours(lmst - expected + 12.0) * 60.0 - 12.0 * 60.0;
+			assert!(minutes.abs() < 5.0, "{longitude}°: {lmst}h");
+		}
+	}
+
+	#[test]
+	fn local_sidereal_time_is_zero_at_sidereal_midnight() {
+		for (longitude, after) in [
+			(0.0, "2024-01-01T00:00:00Z"),
+			(-122.4, "2024-06-15T08:30:00Z"),
+			(151.2, "2031-11-30T23:59:59Z"),
+		] {
+			let after = after.parse::<DateTime<Utc>>().expect("valid instant");
+			let observer = Observer::new(None, longitude);
+			let midnight = observer
+				.next_local_sidereal_time(after, 0.0)
+				.expect("computed for the Earth");
+			assert!(after <= midnight && midnight - after < Duration::days(1));
+			let lmst = observer.lmst(midnight);
+			assert!(
+				lmst.min(24.0 - lmst) * 3600.0 < 0.01,
+				"{longitude}°: {lmst}h"
+			);
+		}
+	}
+}
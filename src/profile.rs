@@ -0,0 +1,131 @@
+//! Timing of the calls into libastro, collected for `--profile`.
+
+use std::{
+	collections::BTreeMap,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Mutex,
+	},
+	time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static STATISTICS: Lazy<Mutex<BTreeMap<&'static str, CallStatistics>>> =
+	Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// How long the calls to one function took.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CallStatistics {
+	pub count: u32,
+	pub min: Duration,
+	pub max: Duration,
+	pub total: Duration,
+}
+
+impl CallStatistics {
+	fn new(elapsed: Duration) -> Self {
+		Self {
+			count: 1,
+			min: elapsed,
+			max: elapsed,
+			total: elapsed,
+		}
+	}
+
+	fn record(&mut self, elapsed: Duration) {
+		self.count += 1;
+		self.min = self.min.min(elapsed);
+		self.max = self.max.max(elapsed);
+		self.total += elapsed;
+	}
+
+	#[must_use]
+	pub fn mean(&self) -> Duration {
+		self.total / self.count
+	}
+}
+
+/// Start timing calls. Until this is called [`time`] only makes the call.
+pub fn enable() {
+	ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Make a call, adding how long it took to the statistics for `name` when timing is enabled.
+pub(crate) fn time<T>(name: &'static str, call: impl FnOnce() -> T) -> T {
+	if !ENABLED.load(Ordering::Relaxed) {
+		return call();
+	}
+	let started = Instant::now();
+	let result = call();
+	let elapsed = started.elapsed();
+	STATISTICS
+		.lock()
+		.expect("statistics lock poisoned")
+		.entry(name)
+		.and_modify(|statistics| statistics.record(elapsed))
+		.or_insert_with(|| CallStatistics::new(elapsed));
+	result
+}
+
+/// The statistics of every function called so far, by name.
+///
+/// # Panics
+///
+/// If a thread panicked while recording a call.
+#[must_use]
+pub fn statistics() -> Vec<(&'static str, CallStatistics)> {
+	STATISTICS
+		.lock()
+		.expect("statistics lock poisoned")
+		.iter()
+		.map(|(name, statistics)| (*name, *statistics))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn recorded(name: &str) -> Option<CallStatistics> {
+		statistics()
+			.into_iter()
+			.find_map(|(recorded, statistics)| (recorded == name).then_some(statistics))
+	}
+
+	#[test]
+	fn calls_add_up_over_iterations() {
+		enable();
+		for delay in [1, 3, 2] {
+			time("a sleep", || {
+				std::thread::sleep(Duration::from_millis(delay));
+			});
+		}
+		let statistics = recorded("a sleep").expect("recorded");
+		assert_eq!(statistics.count, 3);
+		assert!(statistics.min >= Duration::from_millis(1));
+		assert!(statistics.max >= Duration::from_millis(3));
+		assert!(statistics.min <= statistics.mean() && statistics.mean() <= statistics.max);
+		assert!(statistics.total >= Duration::from_millis(6));
+	}
+
+	#[test]
+	fn libastro_calls_are_counted() {
+		enable();
+		let before = recorded("utc_gst").map_or(0, |statistics| statistics.count);
+		let datetime = chrono::NaiveDate::from_ymd_opt(2024, 1, 1)
+			.and_then(|date| date.and_hms_opt(0, 0, 0))
+			.expect("valid date");
+		for minutes in 0..4 {
+			let gmst = crate::astro::greenwich_mean_sidereal_time(
+				datetime + chrono::Duration::minutes(minutes),
+			);
+			assert!(gmst.is_finite());
+		}
+		// Other tests may be calling libastro at the same time.
+		let after = recorded("utc_gst").expect("recorded").count;
+		assert!(after >= before + 4, "{before} then {after}");
+	}
+}
@@ -0,0 +1,81 @@
+//! Arithmetic on times of day expressed as decimal hours.
+
+use chrono::Duration;
+
+/// Map any number of hours onto a single day, the range `[0, 24)`.
+///
+/// Negative inputs wrap backwards, so `-1.0` becomes `23.0`. Non-finite inputs come back as NaN.
+#[must_use]
+pub fn normalize_hours(hours: f64) -> f64 {
+	let normalized = hours.rem_euclid(24.0);
+	// A tiny negative input rounds up to exactly 24.0, which belongs to the next day, and negative
+	// multiples of 24 give -0.0, which has the wrong sign for a time of day.
+	if normalized >= 24.0 || normalized == 0.0 {
+		0.0
+	} else {
+		normalized
+	}
+}
+
+/// Convert decimal hours to a duration, rounded to the nearest nanosecond.
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub fn hours_to_duration(hours: f64) -> Duration {
+	Duration::nanoseconds((hours * 3_600_000_000_000.0).round() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn values_in_the_day_are_unchanged() {
+		for hours in [0.0, 0.5, 12.0, 23.999_999] {
+			assert_eq!(normalize_hours(hours), hours);
+		}
+	}
+
+	#[test]
+	fn negatives_wrap_backwards() {
+		assert_eq!(normalize_hours(-1.0), 23.0);
+		assert_eq!(normalize_hours(-25.5), 22.5);
+		assert_eq!(normalize_hours(-48.0), 0.0);
+	}
+
+	#[test]
+	fn large_values_wrap() {
+		assert_eq!(normalize_hours(24.0), 0.0);
+		assert_eq!(normalize_hours(49.0), 1.0);
+		assert_eq!(normalize_hours(1_000_005.5), 21.5);
+		assert!((0.0..24.0).contains(&normalize_hours(1e300)));
+	}
+
+	#[test]
+	fn zeros_come_back_positive() {
+		for hours in [0.0, -0.0, -24.0, -72.0] {
+			assert_eq!(
+				normalize_hours(hours).to_bits(),
+				0.0_f64.to_bits(),
+				"{hours}"
+			);
+		}
+	}
+
+	#[test]
+	fn tiny_negatives_round_to_midnight() {
+		// 24 minus these is 24.0 in floating point, which belongs to the next day.
+		for hours in [-1e-15, -1e-300, -f64::MIN_POSITIVE] {
+			assert_eq!(normalize_hours(hours), 0.0, "{hours}");
+		}
+		// Big enough to stay below 24.
+		assert!(normalize_hours(-1e-9) < 24.0);
+		assert!(normalize_hours(-1e-9) > 23.9);
+	}
+
+	#[test]
+	fn non_finite_values_are_nan() {
+		for hours in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+			assert!(normalize_hours(hours).is_nan(), "{hours}");
+		}
+	}
+}
@@ -0,0 +1,358 @@
+//! Looking up the civil timezone of a position.
+
+use std::{collections::HashMap, str::FromStr, sync::Mutex};
+
+use anyhow::{anyhow, Result};
+use chrono_tz::Tz;
+use once_cell::sync::Lazy;
+use tzf_rs::DefaultFinder;
+
+/// Width in degrees of the grid cells that share a cached timezone.
+const CACHE_GRID_DEGREES: f64 = 0.01;
+
+/// Deprecated names from the tz database's `backward` file and the zones they link to. chrono-tz
+/// keeps the links as zones of their own, so they would otherwise be shown under the old name.
+const ALIASES: &[(&str, &str)] = &[
+	("Asia/Calcutta", "Asia/Kolkata"),
+	("Asia/Katmandu", "Asia/Kathmandu"),
+	("Asia/Rangoon", "Asia/Yangon"),
+	("Asia/Saigon", "Asia/Ho_Chi_Minh"),
+	("Atlantic/Faeroe", "Atlantic/Faroe"),
+	("Australia/ACT", "Australia/Sydney"),
+	("Australia/NSW", "Australia/Sydney"),
+	("Brazil/East", "America/Sao_Paulo"),
+	("Canada/Atlantic", "America/Halifax"),
+	("Canada/Central", "America/Winnipeg"),
+	("Canada/Eastern", "America/Toronto"),
+	("Canada/Mountain", "America/Edmonton"),
+	("Canada/Pacific", "America/Vancouver"),
+	("Europe/Kiev", "Europe/Kyiv"),
+	("GB", "Europe/London"),
+	("Pacific/Truk", "Pacific/Chuuk"),
+	("US/Alaska", "America/Anchorage"),
+	("US/Arizona", "America/Phoenix"),
+	("US/Central", "America/Chicago"),
+	("US/Eastern", "America/New_York"),
+	("US/Hawaii", "Pacific/Honolulu"),
+	("US/Mountain", "America/Denver"),
+	("US/Pacific", "America/Los_Angeles"),
+];
+
+/// The current IANA zone for a timezone that may be known by a deprecated name, e.g.
+/// `America/Los_Angeles` for `US/Pacific`.
+#[must_use]
+pub fn canonical_timezone(timezone: Tz) -> Tz {
+	ALIASES
+		.iter()
+		.find(|(alias, _)| *alias == timezone.name())
+		.and_then(|(_, canonical)| Tz::from_str(canonical).ok())
+		.unwrap_or(timezone)
+}
+
+static CACHE: Lazy<Mutex<HashMap<(i64, i64), Tz>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[allow(clippy::cast_possible_truncation)]
+fn cache_key(latitude: f64, longitude: f64) -> (i64, i64) {
+	(
+		(latitude / CACHE_GRID_DEGREES).round() as i64,
+		(longitude / CACHE_GRID_DEGREES).round() as i64,
+	)
+}
+
+/// How far in degrees to move a query off the antimeridian, about 10 cm at the equator.
+const ANTIMERIDIAN_NUDGE: f64 = 1e-6;
+
+/// Every IANA zone name the timezone database gives for the coordinates, which is none at sea and
+/// several near some borders.
+#[must_use]
+pub fn timezone_candidates(latitude: f64, longitude: f64) -> Vec<String> {
+	DefaultFinder::new()
+		.get_tz_names(longitude, latitude)
+		.into_iter()
+		.map(str::to_owned)
+		.collect()
+}
+
+/// A source of the timezone names covering a position. The tz database's geometry is behind
+/// [`DefaultFinder`], and a stub can stand in for it to exercise the lookup with chosen candidates.
+pub trait TimezoneFinder {
+	/// Every zone name for the coordinates, in the argument order tzf uses.
+	fn get_tz_names(&self, longitude: f64, latitude: f64) -> Vec<&str>;
+}
+
+impl TimezoneFinder for DefaultFinder {
+	fn get_tz_names(&self, longitude: f64, latitude: f64) -> Vec<&str> {
+		Self::get_tz_names(self, longitude, latitude)
+	}
+}
+
+fn lookup_timezone_at(finder: &impl TimezoneFinder, latitude: f64, longitude: f64) -> Result<Tz> {
+	let timezone = finder.get_tz_names(longitude, latitude);
+	let tz_str = match timezone.len() {
+		0 => Err(anyhow!("No timezones found")),
+		1 => Ok(timezone.first().expect("already checked").to_owned()),
+		_ => Err(anyhow!("Todo: Allow picking a timezone name")),
+	}?;
+	Tz::from_str(tz_str)
+		.map(canonical_timezone)
+		.map_err(|e| anyhow!("Could not convert string: {e}"))
+}
+
+/// Find the timezone for the given coordinates with the given finder, without the cache.
+///
+/// # Errors
+///
+/// Fails when the finder gives no timezone or more than one for the coordinates.
+pub fn lookup_timezone(finder: &impl TimezoneFinder, latitude: f64, longitude: f64) -> Result<Tz> {
+	// Bring the longitude into [-180, 180), so 180° and -180° are the same meridian.
+	let longitude = (longitude + 180.0).rem_euclid(360.0) - 180.0;
+	if 180.0 + longitude > ANTIMERIDIAN_NUDGE {
+		return lookup_timezone_at(finder, latitude, longitude);
+	}
+	// The zone polygons stop at the antimeridian, so a point on it can fall between them. Try
+	// just east of it and then just west, which can be different zones on either side of the
+	// date line.
+	lookup_timezone_at(finder, latitude, -180.0 + ANTIMERIDIAN_NUDGE)
+		.or_else(|_| lookup_timezone_at(finder, latitude, 180.0 - ANTIMERIDIAN_NUDGE))
+}
+
+/// Mean radius of the Earth in kilometres.
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// How many directions each ring of samples in [`nearest_border`] looks in.
+const BORDER_BEARINGS: u32 = 16;
+
+/// How many rings [`nearest_border`] samples out to its radius.
+const BORDER_RINGS: u32 = 40;
+
+/// The position `distance_km` from a point along the great circle leaving it at `bearing`
+/// degrees east of north.
+fn destination(latitude: f64, longitude: f64, bearing: f64, distance_km: f64) -> (f64, f64) {
+	let (phi, lambda) = (latitude.to_radians(), longitude.to_radians());
+	let (theta, delta) = (bearing.to_radians(), distance_km / EARTH_RADIUS_KM);
+	let phi2 = (phi.sin() * delta.cos() + phi.cos() * delta.sin() * theta.cos()).asin();
+	let lambda2 = lambda
+		+ (theta.sin() * delta.sin() * phi.cos()).atan2(delta.cos() - phi.sin() * phi2.sin());
+	(phi2.to_degrees(), lambda2.to_degrees())
+}
+
+/// The approximate distance in kilometres from the coordinates to the nearest place within
+/// `radius_km` that resolves to a different timezone, with that timezone, or `None` when the whole
+/// disc resolves the same way.
+///
+/// The disc is sampled on rings of points in 16 directions, so the distance is only good to
+/// a ring's width and a border cutting between two samples can be missed. Positions where the
+/// lookup fails, at sea or where zones overlap, count as a zone of their own.
+#[must_use]
+pub fn nearest_border(
+	finder: &impl TimezoneFinder,
+	latitude: f64,
+	longitude: f64,
+	radius_km: f64,
+) -> Option<(f64, Option<Tz>)> {
+	let here = lookup_timezone(finder, latitude, longitude).ok();
+	(1..=BORDER_RINGS).find_map(|ring| {
+		let distance = radius_km * f64::from(ring) / f64::from(BORDER_RINGS);
+		(0..BORDER_BEARINGS).find_map(|step| {
+			let bearing = 360.0 * f64::from(step) / f64::from(BORDER_BEARINGS);
+			let (lat, lon) = destination(latitude, longitude, bearing, distance);
+			let there = lookup_timezone(finder, lat, lon).ok();
+			(there != here).then_some((distance, there))
+		})
+	})
+}
+
+/// [`nearest_border`] with the timezone database.
+#[must_use]
+pub fn nearest_timezone_border(
+	latitude: f64,
+	longitude: f64,
+	radius_km: f64,
+) -> Option<(f64, Option<Tz>)> {
+	nearest_border(&DefaultFinder::new(), latitude, longitude, radius_km)
+}
+
+/// Find the timezone for the given coordinates.
+///
+/// Results are cached on a grid of about a kilometre, so a slowly moving position only pays for
+/// a lookup when it crosses into a new cell. Failed lookups are not cached. Deprecated zone names
+/// are replaced by their canonical ones. A position on the antimeridian gets the zone just east of
+/// it, or just west when there is none east.
+///
+/// # Errors
+///
+/// Fails when no single timezone covers the coordinates.
+pub fn get_timezone(latitude: f64, longitude: f64) -> Result<Tz> {
+	let key = cache_key(latitude, longitude);
+	if let Some(timezone) = CACHE.lock().expect("cache lock poisoned").get(&key) {
+		return Ok(*timezone);
+	}
+	let timezone = lookup_timezone(&DefaultFinder::new(), latitude, longitude)?;
+	CACHE
+		.lock()
+		.expect("cache lock poisoned")
+		.insert(key, timezone);
+	Ok(timezone)
+}
+
+/// Forget every cached timezone, e.g. after the timezone database has been updated.
+pub fn clear_timezone_cache() {
+	CACHE.lock().expect("cache lock poisoned").clear();
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A finder that answers with a closure of the longitude and latitude.
+	struct StubFinder<F>(F);
+
+	impl<F: Fn(f64, f64) -> Vec<&'static str>> TimezoneFinder for StubFinder<F> {
+		fn get_tz_names(&self, longitude: f64, latitude: f64) -> Vec<&str> {
+			(self.0)(longitude, latitude)
+		}
+	}
+
+	fn always(
+		names: &'static [&'static str],
+	) -> StubFinder<impl Fn(f64, f64) -> Vec<&'static str>> {
+		StubFinder(move |_, _| names.to_vec())
+	}
+
+	#[test]
+	fn no_candidates_is_an_error() {
+		let error = lookup_timezone(&always(&[]), 0.0, -30.0).expect_err("nothing found");
+		assert!(error.to_string().contains("No timezones found"), "{error}");
+	}
+
+	#[test]
+	fn one_candidate_is_the_zone() {
+		assert_eq!(
+			lookup_timezone(&always(&["Europe/Berlin"]), 52.5, 13.4).ok(),
+			Some(Tz::Europe__Berlin)
+		);
+		assert_eq!(
+			lookup_timezone(&always(&["US/Pacific"]), 37.8, -122.4).ok(),
+			Some(Tz::America__Los_Angeles)
+		);
+		assert!(lookup_timezone(&always(&["Not/A_Zone"]), 0.0, 0.0).is_err());
+	}
+
+	#[test]
+	fn several_candidates_are_an_error() {
+		let finder = always(&["Asia/Shanghai", "Asia/Urumqi"]);
+		assert!(lookup_timezone(&finder, 43.8, 87.6).is_err());
+	}
+
+	#[test]
+	fn antimeridian_tries_east_then_west() {
+		// Apia is just east of the date line and Fiji just west of it. The line itself is never
+		// asked about.
+		let both_sides = StubFinder(|longitude: f64, _: f64| {
+			assert!(longitude.abs() < 180.0, "{longitude}");
+			vec![if longitude < 0.0 {
+				"Pacific/Apia"
+			} else {
+				"Pacific/Fiji"
+			}]
+		});
+		for longitude in [180.0, -180.0] {
+			assert_eq!(
+				lookup_timezone(&both_sides, -14.0, longitude).ok(),
+				Some(Tz::Pacific__Apia)
+			);
+		}
+		let west_only = StubFinder(|longitude: f64, _: f64| {
+			if longitude > 0.0 {
+				vec!["Pacific/Fiji"]
+			} else {
+				vec![]
+			}
+		});
+		assert_eq!(
+			lookup_timezone(&west_only, -17.0, 180.0).ok(),
+			Some(Tz::Pacific__Fiji)
+		);
+		assert!(lookup_timezone(&always(&[]), -17.0, 180.0).is_err());
+	}
+
+	#[test]
+	fn nearby_lookup_is_served_from_the_cache() {
+		// Plant a zone that is wrong for the cell, so only the cache can give it back.
+		let key = cache_key(10.0, 20.0);
+		CACHE
+			.lock()
+			.expect("cache lock poisoned")
+			.insert(key, Tz::Pacific__Honolulu);
+		let timezone = get_timezone(10.002, 20.003);
+		CACHE.lock().expect("cache lock poisoned").remove(&key);
+		assert_eq!(timezone.ok(), Some(Tz::Pacific__Honolulu));
+	}
+
+	#[test]
+	fn antimeridian_resolves_to_a_zone_from_either_side() {
+		let finder = DefaultFinder::new();
+		// Taveuni in Fiji and Chukotka straddle the meridian, and at the equator it is open sea,
+		// where the zone just east of it is 12 hours behind UTC.
+		for (latitude, zone) in [
+			(-16.8, Tz::Pacific__Fiji),
+			(0.0, Tz::Etc__GMTPlus12),
+			(65.0, Tz::Asia__Anadyr),
+		] {
+			for longitude in [180.0, -180.0] {
+				let found = lookup_timezone(&finder, latitude, longitude);
+				assert!(found.is_ok(), "{latitude}, {longitude}: {found:?}");
+				assert_eq!(found.ok(), Some(zone), "{latitude}, {longitude}");
+			}
+		}
+	}
+
+	#[test]
+	fn nearest_border_finds_the_zone_across_it() {
+		// A border along the 10° meridian, about 36 km east of 9.5° at 50° north.
+		let finder = StubFinder(|longitude: f64, _: f64| {
+			vec![if longitude < 10.0 {
+				"Europe/Berlin"
+			} else {
+				"Europe/Warsaw"
+			}]
+		});
+		let (distance, zone) = nearest_border(&finder, 50.0, 9.5, 100.0).expect("within 100 km");
+		assert!((35.0..40.0).contains(&distance), "{distance} km");
+		assert_eq!(zone, Some(Tz::Europe__Warsaw));
+		assert_eq!(nearest_border(&finder, 50.0, 9.5, 20.0), None);
+		assert_eq!(
+			nearest_border(&always(&["Europe/Berlin"]), 50.0, 9.5, 100.0),
+			None
+		);
+	}
+
+	#[test]
+	fn failed_lookups_count_as_a_zone_of_their_own() {
+		// Nothing north of 50.2°, as if it were open sea.
+		let finder = StubFinder(|_: f64, latitude: f64| {
+			if latitude < 50.2 {
+				vec!["Europe/Berlin"]
+			} else {
+				vec![]
+			}
+		});
+		let (distance, zone) = nearest_border(&finder, 50.0, 9.5, 100.0).expect("within 100 km");
+		assert!((20.0..25.0).contains(&distance), "{distance} km");
+		assert_eq!(zone, None);
+	}
+
+	#[test]
+	fn aliases_become_their_canonical_zones() {
+		assert_eq!(
+			canonical_timezone(Tz::US__Pacific),
+			Tz::America__Los_Angeles
+		);
+		assert_eq!(canonical_timezone(Tz::Asia__Calcutta), Tz::Asia__Kolkata);
+		assert_eq!(canonical_timezone(Tz::Europe__Paris), Tz::Europe__Paris);
+		for (alias, canonical) in ALIASES {
+			let timezone = Tz::from_str(alias).expect("alias is in chrono-tz");
+			assert_eq!(canonical_timezone(timezone).name(), *canonical);
+		}
+	}
+}
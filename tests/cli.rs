@@ -0,0 +1,54 @@
+//! Runs of the built binary, for behaviour that depends on where its output goes.
+
+use std::process::{Command, Output};
+
+fn command(args: &[&str]) -> Command {
+	let mut command = Command::new(env!("CARGO_BIN_EXE_sidereal_time_calculator"));
+	command.args(args).env("LANG", "C");
+	command
+}
+
+fn sidtime(args: &[&str]) -> Output {
+	command(args).output().expect("the binary runs")
+}
+
+#[test]
+fn piped_output_has_no_escape_codes() {
+	let output = sidtime(&["--lon", "0", "--max-iterations", "2", "--interval", "10ms"]);
+	assert!(output.status.success(), "{output:?}");
+	assert!(!output.stdout.is_empty());
+	assert!(
+		!output.stdout.contains(&0x1b),
+		"{}",
+		String::from_utf8_lossy(&output.stdout)
+	);
+}
+
+#[test]
+fn output_file_has_no_escape_codes_even_with_color_forced() {
+	let path = std::env::temp_dir().join(format!("sidtime-{}-output.txt", std::process::id()));
+	let path_text = path.to_str().expect("temporary path is UTF-8");
+	let output = command(&["--lon", "0", "--unix", "1700000000", "--output", path_text])
+		.env("CLICOLOR_FORCE", "1")
+		.output()
+		.expect("the binary runs");
+	let written = std::fs::read(&path);
+	std::fs::remove_file(&path).ok();
+	assert!(output.status.success(), "{output:?}");
+	let written = written.expect("the report was written");
+	assert!(!written.is_empty());
+	assert!(
+		!written.contains(&0x1b),
+		"{}",
+		String::from_utf8_lossy(&written)
+	);
+}
+
+#[test]
+fn latitude_without_longitude_fails_with_a_clear_error() {
+	let output = sidtime(&["--lat", "51.5", "--max-iterations", "1"]);
+	assert!(!output.status.success(), "{output:?}");
+	let stderr = String::from_utf8_lossy(&output.stderr);
+	assert!(stderr.contains("--lat requires --lon"), "{stderr}");
+	assert!(output.stdout.is_empty(), "{output:?}");
+}